@@ -1,8 +1,20 @@
-use tower_lsp::lsp_types::{CompletionItem, InsertTextFormat};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, InsertTextFormat,
+};
+
+use crate::{
+    documentation::{self, QuteDocumentation},
+    parser::{
+        java_type::{JavaMember, JavaMemberKind},
+        qute::{parse_expression, LineIndex, QuteDocument},
+    },
+    type_resolver::TypeResolver,
+};
 
 struct Completable<'a> {
     label: &'a str,
     detail: &'a str,
+    documentation: QuteDocumentation,
 }
 
 impl Completable<'_> {
@@ -12,7 +24,7 @@ impl Completable<'_> {
             label_details: None,
             kind: None,
             detail: Some(self.detail.to_string()),
-            documentation: None,
+            documentation: Some(self.documentation.to_lsp()),
             deprecated: None,
             preselect: None,
             sort_text: None,
@@ -37,7 +49,7 @@ impl From<&Completable<'_>> for CompletionItem {
             label_details: None,
             kind: None,
             detail: Some(value.detail.to_string()),
-            documentation: None,
+            documentation: Some(value.documentation.to_lsp()),
             deprecated: None,
             preselect: None,
             sort_text: None,
@@ -81,6 +93,7 @@ macro_rules! keyword {
             Completable {
                 label: $x.complete(),
                 detail: $x.detail(),
+                documentation: $x.documentation(),
             },
             $x.complete(),
         )
@@ -132,6 +145,27 @@ ${0:<h1>Fragment works</h1>}
             Keyword::Cached => "{#cached}$0{/cached}",
         }
     }
+    pub const fn documentation(&self) -> QuteDocumentation {
+        match self {
+            Keyword::Comment => documentation::COMMENT,
+            Keyword::Variable => documentation::EXPRESSION,
+            Keyword::DoubleVariable => documentation::EXPRESSION,
+            Keyword::ForLoop => documentation::FOR,
+            Keyword::Each => documentation::EACH,
+            Keyword::Let => documentation::LET,
+            Keyword::If => documentation::IF,
+            Keyword::Else => documentation::ELSE,
+            Keyword::When => documentation::WHEN,
+            Keyword::Is => documentation::IS,
+            Keyword::IsIn => documentation::IS,
+            Keyword::Switch => documentation::SWITCH,
+            Keyword::Case => documentation::CASE,
+            Keyword::With => documentation::WITH,
+            Keyword::Include => documentation::INCLUDE,
+            Keyword::Fragment => documentation::FRAGMENT,
+            Keyword::Cached => documentation::CACHED,
+        }
+    }
     pub const fn detail(&self) -> &'static str {
         match self {
             Keyword::Comment => "The content of a comment is completely ignored when rendering the output.",
@@ -155,65 +189,153 @@ ${0:<h1>Fragment works</h1>}
     }
 }
 
-pub fn completion(line: String, char_pos: usize) -> Vec<CompletionItem> {
-    let chars = get_characters_before(line, char_pos);
-    let chars = chars.trim();
-
-    let chars = chars.trim_start_matches([
-        ' ', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q',
-        'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-    ]);
-
+/// The keywords that start with the tag typed before the offset. `{#f` completes `{#for` and
+/// `{#fragment` and only the rest of the keyword is inserted.
+pub fn completion(document: &QuteDocument, content: &str, offset: usize) -> Vec<CompletionItem> {
+    let Some((start, _)) = document.tag_at(content, offset) else {
+        return vec![];
+    };
+    let typed = &content[start..offset];
+    if typed.contains(|c: char| c.is_whitespace() || c == '}') {
+        return vec![];
+    }
     KEYWODS
         .iter()
-        .filter(|(_, v)| v.contains(chars))
-        // remove prefix if already typed
-        .map(|(c, _)| {
-            if c.label.starts_with(chars) {
-                return c.to_lsp(&c.label[chars.len()..]);
-            }
-            c.to_lsp(c.label)
-        })
+        .filter(|(_, v)| v.starts_with(typed))
+        .map(|(c, _)| c.to_lsp(&c.label[typed.len()..]))
         .collect()
 }
 
-fn get_characters_before(line: String, char_pos: usize) -> String {
-    let char_pos = char_pos.min(line.len());
-    let start_pos = if char_pos >= 3 { char_pos - 3 } else { 0 };
-    let length = char_pos - start_pos;
-    line[start_pos..char_pos]
-        .chars()
-        .take(length)
-        .collect::<String>()
+/// Members of the java type of the expression before the dot at the offset. `{item.` completes
+/// the fields, record components, getters and methods of the type of `item`.
+pub fn member_completion(
+    content: &str,
+    offset: usize,
+    resolver: &TypeResolver,
+) -> Vec<CompletionItem> {
+    let Some(before) = content.get(..offset) else {
+        return vec![];
+    };
+    // only inside of a tag
+    if before.rfind('{') <= before.rfind('}') {
+        return vec![];
+    }
+    let chain_start = before
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == '.'))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    // `{inject:bean.` is resolved by the namespace
+    if before[..chain_start].ends_with(':') {
+        return vec![];
+    }
+    let Some(dot) = before[chain_start..].rfind('.') else {
+        return vec![];
+    };
+    if dot == 0 {
+        return vec![];
+    }
+    let expression = parse_expression(&LineIndex::new(content), chain_start, chain_start + dot);
+    let types = resolver.part_types(&expression);
+    if types.is_empty() || types.len() != expression.parts.len() {
+        return vec![];
+    }
+    let mut out: Vec<CompletionItem> = vec![];
+    for member in resolver.types.members(&types[types.len() - 1]) {
+        // a field hides the getter of the same name
+        if out.iter().any(|i| i.label == member.name) {
+            continue;
+        }
+        out.push(member_item(member));
+    }
+    out
+}
+
+/// Methods with parameters are inserted as snippets with a placeholder for every parameter
+fn member_item(member: JavaMember) -> CompletionItem {
+    let (kind, parameters, description) = match &member.kind {
+        JavaMemberKind::Field => (CompletionItemKind::FIELD, None, None),
+        JavaMemberKind::RecordComponent => (CompletionItemKind::FIELD, None, None),
+        JavaMemberKind::Getter(method) => (
+            CompletionItemKind::PROPERTY,
+            None,
+            Some(format!("{method}()")),
+        ),
+        JavaMemberKind::Method(parameters) if parameters.is_empty() => {
+            (CompletionItemKind::METHOD, None, None)
+        }
+        JavaMemberKind::Method(parameters) => {
+            (CompletionItemKind::METHOD, Some(parameters.clone()), None)
+        }
+    };
+    let insert_text = parameters.as_ref().map(|parameters| {
+        let placeholders: Vec<String> = parameters
+            .iter()
+            .enumerate()
+            .map(|(i, p)| format!("${{{}:{p}}}", i + 1))
+            .collect();
+        format!("{}({})", member.name, placeholders.join(", "))
+    });
+    CompletionItem {
+        label_details: Some(CompletionItemLabelDetails {
+            detail: parameters.map(|p| format!("({})", p.join(", "))),
+            description,
+        }),
+        kind: Some(kind),
+        detail: Some(member.java_type),
+        insert_text_format: insert_text.as_ref().map(|_| InsertTextFormat::SNIPPET),
+        insert_text,
+        label: member.name,
+        ..Default::default()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::completion;
+    use super::{completion, member_completion};
     use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Url;
+
+    use crate::{
+        parser::{
+            checked_template::scan_checked_templates, checked_template_index::CheckedTemplateIndex,
+            java_type::scan_java_types, java_type_index::JavaTypeIndex, qute,
+        },
+        type_resolver::TypeResolver,
+    };
 
     #[test]
     fn completion_crash() {
-        let line = "{\r\n".to_string();
-        assert_eq!(completion(line, 1).len(), 17);
+        let line = "{\r\n";
+        assert_eq!(completion(&qute::parse(line), line, 1).len(), 17);
 
-        let line = "{#".to_string();
-        assert_eq!(completion(line, 2).len(), 14);
+        let line = "{#";
+        assert_eq!(completion(&qute::parse(line), line, 2).len(), 14);
 
-        let line = "{#f".to_string();
-        assert_eq!(completion(line, 3).len(), 2);
+        let line = "{#f";
+        assert_eq!(completion(&qute::parse(line), line, 3).len(), 2);
 
-        let line = "{@if".to_string();
-        assert_eq!(completion(line, 4).len(), 0);
+        let line = "{@if";
+        assert_eq!(completion(&qute::parse(line), line, 4).len(), 0);
 
-        let line = "{@whe".to_string();
-        assert_eq!(completion(line, 4).len(), 0);
+        let line = "{@whe";
+        assert_eq!(completion(&qute::parse(line), line, 4).len(), 0);
+    }
+
+    #[test]
+    fn completion_documentation() {
+        let line = "{#fo";
+        let out = completion(&qute::parse(line), line, 4);
+        assert_eq!(
+            out[0].documentation,
+            Some(crate::documentation::FOR.to_lsp())
+        );
     }
 
     #[test]
     fn completion_trim_prefix() {
-        let line = "{".to_string();
-        let out = completion(line, 1);
+        let line = "{";
+        let out = completion(&qute::parse(line), line, 1);
         let out: Vec<Option<String>> = out.into_iter().map(|e| e.insert_text).collect();
         assert_eq!(
             out,
@@ -241,8 +363,8 @@ mod tests {
 
     #[test]
     fn completion_trim_prefix_with_unwanted() {
-        let line = "df{".to_string();
-        let out = completion(line, 3);
+        let line = "df{";
+        let out = completion(&qute::parse(line), line, 3);
         let out: Vec<Option<String>> = out.into_iter().map(|e| e.insert_text).collect();
         assert_eq!(
             out,
@@ -267,4 +389,59 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn completion_of_members() {
+        let java = "package org.acme;
+public class Item {
+    public String name;
+    public List<Item> children;
+    public BigDecimal getPrice() { return price; }
+    public String label(String prefix, int limit) { return prefix; }
+}
+public class ItemResource {
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items);
+    }
+}";
+        let types = JavaTypeIndex::new();
+        let checked_templates = CheckedTemplateIndex::new();
+        let uri = Url::parse("file:///project/Item.java").unwrap();
+        types.replace_file(uri.clone(), scan_java_types(java));
+        checked_templates.replace_file(uri, scan_checked_templates(java));
+        let members = |content: &str| {
+            let document = qute::parse(content);
+            let resolver = TypeResolver::new(
+                content,
+                &document,
+                "ItemResource/list",
+                &checked_templates,
+                &types,
+            );
+            member_completion(content, content.len(), &resolver)
+                .into_iter()
+                .map(|i| (i.label, i.detail.unwrap_or_default(), i.insert_text))
+                .collect::<Vec<_>>()
+        };
+        let item_members = vec![
+            ("name".to_string(), "String".to_string(), None),
+            (
+                "children".to_string(),
+                "List<org.acme.Item>".to_string(),
+                None,
+            ),
+            ("price".to_string(), "BigDecimal".to_string(), None),
+            (
+                "label".to_string(),
+                "String".to_string(),
+                Some("label(${1:String}, ${2:int})".to_string()),
+            ),
+        ];
+        assert_eq!(members("{@org.acme.Item item}\n{item."), item_members);
+        assert_eq!(members("{#for item in items}{item.children.na"), vec![]);
+        assert_eq!(members("{#for item in items}\n<p>{item."), item_members);
+        assert_eq!(members("{#each items}{it.name."), vec![]);
+        assert_eq!(members("{@org.acme.Item item} item."), vec![]);
+    }
 }
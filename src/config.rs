@@ -1,4 +1,15 @@
-use clap::Parser;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::workspace::{JAVA_FOLDER, TEMPLATE_FOLDER};
 
 /// A build log analysis tool
 #[derive(Parser, Debug)]
@@ -7,4 +18,210 @@ pub struct Args {
     /// prints routes as json
     #[clap(long)]
     pub get_routes: bool,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// formats templates in place
+    Format {
+        files: Vec<PathBuf>,
+        /// only prints the files that are not formatted and fails if there are any
+        #[clap(long)]
+        check: bool,
+        /// spaces per indentation level
+        #[clap(long, default_value_t = 4)]
+        tab_size: usize,
+    },
+}
+
+/// Name of the project configuration file in the workspace root
+pub static CONFIG_FILE: &str = "qute-lsp.toml";
+
+/// Configuration of the project. Read from `qute-lsp.toml` in the workspace root. The settings
+/// of the editor (`initializationOptions` and `workspace/didChangeConfiguration`) override it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Template folders relative to each module
+    pub template_roots: Vec<String>,
+    /// Java source folders relative to each module
+    pub source_roots: Vec<String>,
+    /// File extensions of templates without the dot
+    pub template_suffixes: Vec<String>,
+    /// Attributes that contain a path to a route in addition to `action` and the htmx ones
+    pub route_attributes: Vec<String>,
+    /// Severity by diagnostic code. `unknown-route = "off"`
+    pub severities: HashMap<String, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            template_roots: vec![TEMPLATE_FOLDER.to_string()],
+            source_roots: vec![JAVA_FOLDER.to_string()],
+            template_suffixes: vec!["html".to_string()],
+            route_attributes: vec![],
+            severities: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+    /// The diagnostic is not reported
+    Off,
+}
+
+impl Severity {
+    pub fn to_lsp(self) -> Option<DiagnosticSeverity> {
+        match self {
+            Severity::Error => Some(DiagnosticSeverity::ERROR),
+            Severity::Warning => Some(DiagnosticSeverity::WARNING),
+            Severity::Information => Some(DiagnosticSeverity::INFORMATION),
+            Severity::Hint => Some(DiagnosticSeverity::HINT),
+            Severity::Off => None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the configuration file in the root. The default is used when there is no file or
+    /// it is invalid.
+    pub fn load(root: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(root.join(CONFIG_FILE)) else {
+            return Self::default();
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Unable to read {CONFIG_FILE}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Overrides the values that are set in the settings of the editor. The settings can be
+    /// nested in a `qute-lsp` object.
+    pub fn merge(&self, settings: &Value) -> Self {
+        let settings = settings.get("qute-lsp").unwrap_or(settings);
+        let (Ok(Value::Object(mut out)), Value::Object(settings)) =
+            (serde_json::to_value(self), settings)
+        else {
+            return self.clone();
+        };
+        for (key, value) in settings {
+            out.insert(key.clone(), value.clone());
+        }
+        match serde_json::from_value(Value::Object(out)) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Unable to read the settings of the editor: {e}");
+                self.clone()
+            }
+        }
+    }
+
+    pub fn is_template_suffix(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| self.template_suffixes.iter().any(|s| s == e))
+    }
+
+    /// Applies the configured severities. Diagnostics that are turned off are removed.
+    pub fn apply_severities(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                    return Some(diagnostic);
+                };
+                if let Some(severity) = self.severities.get(code) {
+                    diagnostic.severity = Some(severity.to_lsp()?);
+                }
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+    use super::{Config, Severity};
+
+    #[test]
+    fn from_toml() {
+        let content = "template_roots = [\"src/main/resources/templates/\", \"templates/\"]
+template_suffixes = [\"html\", \"txt\"]
+
+[severities]
+unknown-route = \"off\"
+";
+        let config: Config = toml::from_str(content).unwrap();
+        assert_eq!(
+            config.template_roots,
+            vec!["src/main/resources/templates/", "templates/"]
+        );
+        assert_eq!(config.source_roots, Config::default().source_roots);
+        assert_eq!(config.severities.get("unknown-route"), Some(&Severity::Off));
+        assert!(config.is_template_suffix(Path::new("mail.txt")));
+        assert!(!config.is_template_suffix(Path::new(".DS_Store")));
+    }
+
+    #[test]
+    fn merge_settings() {
+        let config = Config::default().merge(&json!({
+            "qute-lsp": {
+                "route_attributes": ["data-url"],
+                "severities": { "unknown-fragment": "warning" }
+            }
+        }));
+        assert_eq!(config.route_attributes, vec!["data-url"]);
+        assert_eq!(config.template_roots, Config::default().template_roots);
+        assert_eq!(
+            config.severities.get("unknown-fragment"),
+            Some(&Severity::Warning)
+        );
+        // invalid settings are ignored
+        assert_eq!(
+            config.merge(&json!({ "template_roots": 1 })),
+            config.clone()
+        );
+    }
+
+    #[test]
+    fn apply_severities() {
+        let config = Config::default().merge(&json!({
+            "severities": { "unknown-route": "off", "unknown-fragment": "hint" }
+        }));
+        let diagnostic = |code: &str| Diagnostic {
+            code: Some(NumberOrString::String(code.to_string())),
+            severity: Some(DiagnosticSeverity::ERROR),
+            ..Default::default()
+        };
+        let out = config.apply_severities(vec![
+            diagnostic("unknown-route"),
+            diagnostic("unknown-fragment"),
+            diagnostic("unclosed-section"),
+        ]);
+        assert_eq!(
+            out.iter().map(|d| d.severity).collect::<Vec<_>>(),
+            vec![
+                Some(DiagnosticSeverity::HINT),
+                Some(DiagnosticSeverity::ERROR)
+            ]
+        );
+    }
 }
@@ -0,0 +1,547 @@
+use std::{collections::HashMap, path::Path};
+
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, DiagnosticRelatedInformation,
+    DiagnosticSeverity, Location, NumberOrString, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::{
+    parser::{
+        fragemnt::Fragment,
+        include::{include_from_section, QuteInclude},
+        qute::{Expression, ExpressionPartKind, ParseErrorKind, QuteDocument, QuteNode},
+        route_attribute::{attribute_for_http_method, RouteAttribute},
+        route_index::RouteIndex,
+    },
+    string_utils::closest,
+    type_resolver::TypeResolver,
+};
+
+/// Diagnostics for sections that are not closed or closed by the wrong end tag
+pub fn section_diagnostics(uri: &Url, document: &QuteDocument) -> Vec<Diagnostic> {
+    document
+        .errors
+        .iter()
+        .map(|error| {
+            let related_information = error.related.as_ref().map(|related| {
+                vec![DiagnosticRelatedInformation {
+                    location: Location::new(uri.clone(), related.range),
+                    message: "Section opened here".to_string(),
+                }]
+            });
+            Diagnostic {
+                range: error.span.range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String(error_code(&error.kind).to_string())),
+                source: Some("qute-lsp".to_string()),
+                message: error.kind.to_string(),
+                related_information,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn error_code(kind: &ParseErrorKind) -> &'static str {
+    match kind {
+        ParseErrorKind::UnclosedSection(_) => "unclosed-section",
+        ParseErrorKind::StrayEndTag(_) => "stray-end-tag",
+        ParseErrorKind::MismatchedEndTag { .. } => "mismatched-end-tag",
+        ParseErrorKind::UnexpectedSectionBlock(_) => "unexpected-section-block",
+        ParseErrorKind::UnterminatedTag
+        | ParseErrorKind::UnterminatedComment
+        | ParseErrorKind::UnterminatedUnparsed => "unterminated-tag",
+    }
+}
+
+/// Diagnostics for includes of templates or fragments that do not exist
+pub fn include_diagnostics(
+    document: &QuteDocument,
+    current_template: Option<&str>,
+    templates: &[String],
+    fragments: &[Fragment],
+) -> Vec<Diagnostic> {
+    document
+        .sections()
+        .into_iter()
+        .filter_map(|section| {
+            let include = include_from_section(section)?;
+            let range = section
+                .parameters()
+                .iter()
+                .find(|p| p.name.is_none())?
+                .value_span
+                .range;
+            match include {
+                QuteInclude::Basic(template) => {
+                    if template_exists(templates, &template) {
+                        return None;
+                    }
+                    let suggestion = closest(&template, templates.iter().map(String::as_str));
+                    Some(reference_diagnostic(
+                        range,
+                        "unknown-template",
+                        format!("Template {template} does not exist"),
+                        suggestion.map(str::to_string),
+                    ))
+                }
+                QuteInclude::Fragment(fragment) => {
+                    // `{#include $item /}` references a fragment of the current template
+                    let template = match fragment.template.as_str() {
+                        "" => current_template?,
+                        template => template,
+                    };
+                    if !template_exists(templates, template) {
+                        let suggestion = closest(template, templates.iter().map(String::as_str));
+                        return Some(reference_diagnostic(
+                            range,
+                            "unknown-template",
+                            format!("Template {template} does not exist"),
+                            suggestion.map(|s| format!("{s}${}", fragment.fragment)),
+                        ));
+                    }
+                    let id = format!("{template}${}", fragment.fragment);
+                    if fragments.iter().any(|f| f.id == id) {
+                        return None;
+                    }
+                    let suggestion = closest(&id, fragments.iter().map(|f| f.id.as_str()));
+                    Some(reference_diagnostic(
+                        range,
+                        "unknown-fragment",
+                        format!(
+                            "Fragment {} does not exist in template {template}",
+                            fragment.fragment
+                        ),
+                        suggestion.map(str::to_string),
+                    ))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Warnings for htmx and form attributes whose path does not match any route of the project
+pub fn route_diagnostics(
+    attributes: &[RouteAttribute],
+    route_index: &RouteIndex,
+) -> Vec<Diagnostic> {
+    attributes
+        .iter()
+        .filter_map(|attribute| {
+            let path = attribute.path()?;
+            if route_index.contains(path) {
+                return None;
+            }
+            Some(Diagnostic {
+                range: attribute.value_span.range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String("unknown-route".to_string())),
+                source: Some("qute-lsp".to_string()),
+                message: format!("No route matches {path}"),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Warnings for attributes that call a path with a http method the route does not implement
+pub fn route_method_diagnostics(
+    attributes: &[RouteAttribute],
+    route_index: &RouteIndex,
+    module: Option<&Path>,
+) -> Vec<Diagnostic> {
+    attributes
+        .iter()
+        .filter_map(|attribute| {
+            let path = attribute.path()?;
+            let method = attribute.method.as_ref()?;
+            let routes = route_index.find(path, module);
+            if routes.is_empty() || routes.iter().any(|r| &r.method == method) {
+                return None;
+            }
+            let methods: Vec<String> = routes.iter().map(|r| r.method.to_string()).collect();
+            // forms can not be changed by renaming the attribute
+            let replacement = match attribute.name.as_str() {
+                "action" => None,
+                _ => routes
+                    .iter()
+                    .find_map(|r| attribute_for_http_method(&r.method)),
+            };
+            Some(Diagnostic {
+                range: attribute.name_span.range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String("http-method-mismatch".to_string())),
+                source: Some("qute-lsp".to_string()),
+                message: format!(
+                    "There is no {} route for {path}. Available methods: {}",
+                    method.to_string(),
+                    methods.join(", ")
+                ),
+                data: replacement.map(|r| json!({ "replacement": r })),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Errors for properties the java type of an expression does not have. `{item.nmae}` for
+/// `{@org.acme.Item item}`. Types with supertypes outside of the project are not checked.
+pub fn property_diagnostics(document: &QuteDocument, resolver: &TypeResolver) -> Vec<Diagnostic> {
+    let mut expressions: Vec<Expression> = vec![];
+    for node in document.walk() {
+        match node {
+            QuteNode::Expression(expression) => expressions.push(expression.clone()),
+            QuteNode::Section(section) => expressions.extend(resolver.iterable(section)),
+            _ => (),
+        }
+    }
+    let mut out = vec![];
+    while let Some(expression) = expressions.pop() {
+        for part in &expression.parts {
+            if let ExpressionPartKind::VirtualMethod(parameters) = &part.kind {
+                expressions.extend(parameters.iter().cloned());
+            }
+        }
+        out.extend(unknown_property(&expression, resolver));
+    }
+    out.sort_by_key(|d| d.range.start);
+    out
+}
+
+/// Properties every object has. The ones of `java.lang.Object` and the virtual methods qute
+/// adds to every value.
+const OBJECT_PROPERTIES: [&str; 7] = [
+    "toString", "hashCode", "getClass", "class", "raw", "safe", "orEmpty",
+];
+
+fn unknown_property(expression: &Expression, resolver: &TypeResolver) -> Option<Diagnostic> {
+    let types = resolver.part_types(expression);
+    let owner = types.last()?;
+    let part = expression.parts.get(types.len())?;
+    if part.kind != ExpressionPartKind::Property
+        || OBJECT_PROPERTIES.contains(&part.name.as_str())
+        || !resolver.types.is_complete(owner)
+    {
+        return None;
+    }
+    let owner = resolver.types.find(owner)?.qualified_name;
+    Some(Diagnostic {
+        range: part.span.range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String("unknown-property".to_string())),
+        source: Some("qute-lsp".to_string()),
+        message: format!("Property {} does not exist on {owner}", part.name),
+        ..Default::default()
+    })
+}
+
+/// Templates can be included with or without the file suffix
+fn template_exists(templates: &[String], reference: &str) -> bool {
+    let without_suffix = reference
+        .rsplit_once('.')
+        .map(|(name, _)| name)
+        .unwrap_or(reference);
+    templates
+        .iter()
+        .any(|t| t == reference || t == without_suffix)
+}
+
+fn reference_diagnostic(
+    range: Range,
+    code: &str,
+    message: String,
+    suggestion: Option<String>,
+) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("qute-lsp".to_string()),
+        message,
+        data: suggestion.map(|s| json!({ "replacement": s })),
+        ..Default::default()
+    }
+}
+
+/// Quick fixes for diagnostics that carry a replacement
+pub fn quick_fixes(uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| {
+            let replacement = diagnostic.data.as_ref()?.get("replacement")?;
+            let Value::String(replacement) = replacement else {
+                return None;
+            };
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Change to {replacement}"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        uri.clone(),
+                        vec![TextEdit::new(diagnostic.range, replacement.clone())],
+                    )])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Location, Position, Range, Url};
+
+    use crate::{
+        parser::{
+            checked_template_index::CheckedTemplateIndex,
+            fragemnt::Fragment,
+            java_type::scan_java_types,
+            java_type_index::JavaTypeIndex,
+            qute,
+            route::{HttpMethod, Route},
+            route_attribute::scan_route_attributes,
+            route_index::RouteIndex,
+        },
+        type_resolver::TypeResolver,
+    };
+
+    use super::{
+        include_diagnostics, property_diagnostics, route_diagnostics, route_method_diagnostics,
+        section_diagnostics,
+    };
+
+    #[test]
+    fn mismatched_end_tag_points_to_opener() {
+        let uri = Url::parse("file:///templates/items.html").unwrap();
+        let document = qute::parse("{#each items}\n<li>{it}</li>\n{/for}");
+        let out = section_diagnostics(&uri, &document);
+        assert_eq!(out.len(), 1);
+        assert_eq!(
+            out[0].message,
+            "End tag {/for} does not match section {#each}"
+        );
+        assert_eq!(
+            out[0].range,
+            Range::new(Position::new(2, 0), Position::new(2, 6))
+        );
+        let related = out[0].related_information.as_ref().unwrap();
+        assert_eq!(
+            related[0].location.range,
+            Range::new(Position::new(0, 0), Position::new(0, 13))
+        );
+    }
+
+    #[test]
+    fn balanced_has_no_diagnostics() {
+        let uri = Url::parse("file:///templates/items.html").unwrap();
+        let document = qute::parse("{#if a}{#for i in b}{i}{/for}{#else}-{/if}");
+        assert_eq!(section_diagnostics(&uri, &document), vec![]);
+    }
+
+    #[test]
+    fn include_unknown_template_and_fragment() {
+        let templates = vec!["index".to_string(), "snippets/tailwind".to_string()];
+        let fragments = vec![Fragment {
+            id: "index$item".to_string(),
+            source: String::new(),
+            range: Range::default(),
+        }];
+        let document = qute::parse(
+            "{#include snippets/tailwind /}\n{#include snipets/tailwind /}\n{#include index$iten /}\n{#include index$item /}",
+        );
+        let out = include_diagnostics(&document, None, &templates, &fragments);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec![
+                "Template snipets/tailwind does not exist",
+                "Fragment iten does not exist in template index"
+            ]
+        );
+        assert_eq!(
+            out[0].range,
+            Range::new(Position::new(1, 10), Position::new(1, 26))
+        );
+        assert_eq!(
+            out.iter()
+                .map(|d| d.data.as_ref().unwrap()["replacement"].as_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["snippets/tailwind", "index$item"]
+        );
+    }
+
+    #[test]
+    fn include_fragment_of_current_template() {
+        let templates = vec!["index".to_string()];
+        let fragments = vec![Fragment {
+            id: "index$item".to_string(),
+            source: String::new(),
+            range: Range::default(),
+        }];
+        let document = qute::parse("{#include $item /}");
+        let out = include_diagnostics(&document, Some("index"), &templates, &fragments);
+        assert_eq!(out, vec![]);
+    }
+
+    #[test]
+    fn route_unknown_path() {
+        let route_index = RouteIndex::new();
+        route_index.insert(Route {
+            path: "/hello/customer/{name}".to_string(),
+            ..Default::default()
+        });
+        let attributes = scan_route_attributes(
+            "<button hx-get=\"/hello/customer/{c.name}\"></button>\n<button hx-get=\"/hello/client/{c.name}\"></button>",
+            &[],
+        );
+        let out = route_diagnostics(&attributes, &route_index);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec!["No route matches /hello/client/{c.name}"]
+        );
+        assert_eq!(
+            out[0].range,
+            Range::new(Position::new(1, 16), Position::new(1, 38))
+        );
+    }
+
+    #[test]
+    fn route_of_other_module() {
+        let route_index = RouteIndex::new();
+        let path = "/project/api/src/main/java/org/acme/ItemResource.java";
+        let uri = Url::from_file_path(path).unwrap();
+        route_index.replace_file(
+            uri.as_str(),
+            Path::new("/project/api"),
+            vec![Route {
+                method: HttpMethod::Post,
+                path: "/items/{id}".to_string(),
+                implementation: Some(Location::new(uri.clone(), Range::default())),
+                ..Default::default()
+            }],
+        );
+        // a template of the web module
+        let attributes = scan_route_attributes(
+            "<button hx-post=\"/items/{item.id}\"></button>\n<button hx-post=\"/orders\"></button>",
+            &[],
+        );
+        let out = route_diagnostics(&attributes, &route_index);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec!["No route matches /orders"]
+        );
+        let out =
+            route_method_diagnostics(&attributes, &route_index, Some(Path::new("/project/web")));
+        assert_eq!(out, vec![]);
+    }
+
+    #[test]
+    fn route_method_mismatch() {
+        let route_index = RouteIndex::new();
+        route_index.insert(Route {
+            method: HttpMethod::Get,
+            path: "/hello/customer/{name}".to_string(),
+            ..Default::default()
+        });
+        let attributes = scan_route_attributes(
+            "<button hx-post=\"/hello/customer/{c.id}\"></button>\n<button hx-get=\"/hello/customer/{c.id}\"></button>\n<form action=\"/hello/customer/{c.id}\" method=\"post\"></form>",
+            &[],
+        );
+        let out = route_method_diagnostics(&attributes, &route_index, None);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec![
+                "There is no POST route for /hello/customer/{c.id}. Available methods: GET",
+                "There is no POST route for /hello/customer/{c.id}. Available methods: GET"
+            ]
+        );
+        assert_eq!(
+            out[0].range,
+            Range::new(Position::new(0, 8), Position::new(0, 15))
+        );
+        assert_eq!(
+            out[0].data.as_ref().unwrap()["replacement"].as_str(),
+            Some("hx-get")
+        );
+        assert_eq!(out[1].data, None);
+    }
+
+    #[test]
+    fn unknown_properties() {
+        let types = JavaTypeIndex::new();
+        types.replace_file(
+            Url::parse("file:///project/Item.java").unwrap(),
+            scan_java_types(
+                "package org.acme;
+public class Item {
+    public String name;
+    public List<Item> children;
+    public String label(String prefix) { return prefix; }
+}
+public class Shop extends PanacheEntity {}",
+            ),
+        );
+        let checked_templates = CheckedTemplateIndex::new();
+        let content = "{@org.acme.Item item}{@org.acme.Shop shop}
+{item.nmae} {item.name.length} {shop.id} {item.label(item.titel)}
+{#for child in item.childs}{/for}
+{#for child in item.children}{child.name ?: child.nam}{/for}";
+        let document = qute::parse(content);
+        let resolver = TypeResolver::new(content, &document, "items", &checked_templates, &types);
+        let out = property_diagnostics(&document, &resolver);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec![
+                "Property nmae does not exist on org.acme.Item",
+                "Property titel does not exist on org.acme.Item",
+                "Property childs does not exist on org.acme.Item",
+                "Property nam does not exist on org.acme.Item",
+            ]
+        );
+        assert_eq!(
+            out[0].range,
+            Range::new(Position::new(1, 6), Position::new(1, 10))
+        );
+    }
+
+    #[test]
+    fn implicit_properties_are_known() {
+        let types = JavaTypeIndex::new();
+        types.replace_file(
+            Url::parse("file:///project/Item.java").unwrap(),
+            scan_java_types(
+                "package org.acme;
+public class Item {
+    public Status status;
+    public String name;
+}
+public enum Status { OPEN, CLOSED }
+@Data
+public class Tag {
+    private String label;
+}
+public class Label {
+    @Getter
+    private String text;
+}",
+            ),
+        );
+        let checked_templates = CheckedTemplateIndex::new();
+        let content = "{@org.acme.Item item}{@org.acme.Tag tag}{@org.acme.Label label}
+{item.status.name} {item.status.ordinal} {item.toString} {item.name.raw} {item.safe}
+{tag.label} {label.text} {item.status.nmae}";
+        let document = qute::parse(content);
+        let resolver = TypeResolver::new(content, &document, "items", &checked_templates, &types);
+        let out = property_diagnostics(&document, &resolver);
+        assert_eq!(
+            out.iter().map(|d| d.message.as_str()).collect::<Vec<_>>(),
+            vec!["Property nmae does not exist on org.acme.Status"]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use tower_lsp::lsp_types::{Documentation, MarkupContent, MarkupKind};
+
+const QUTE_REFERENCE: &str = "https://quarkus.io/guides/qute-reference";
+
+/// Documentation of a qute construct with an example and a link into the qute reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuteDocumentation {
+    pub title: &'static str,
+    pub summary: &'static str,
+    pub example: &'static str,
+    /// Anchor in the qute reference
+    pub anchor: &'static str,
+}
+
+impl QuteDocumentation {
+    pub fn markdown(&self) -> String {
+        format!(
+            "**{}**\n\n{}\n\n```html\n{}\n```\n\n[Qute reference]({}#{})",
+            self.title, self.summary, self.example, QUTE_REFERENCE, self.anchor
+        )
+    }
+
+    pub fn to_lsp(&self) -> Documentation {
+        Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: self.markdown(),
+        })
+    }
+}
+
+pub const COMMENT: QuteDocumentation = QuteDocumentation {
+    title: "{! comment !}",
+    summary: "The content of a comment is completely ignored when rendering the output.",
+    example: "{! This is a comment !}",
+    anchor: "comments",
+};
+
+pub const EXPRESSION: QuteDocumentation = QuteDocumentation {
+    title: "{expression}",
+    summary: "An expression outputs a value. It consists of an optional namespace followed by a colon and several parts separated by dots.",
+    example: "{item.name}\n{inject:greeter.hello}",
+    anchor: "expressions",
+};
+
+pub const FOR: QuteDocumentation = QuteDocumentation {
+    title: "{#for}",
+    summary: "The loop section iterates over an `Iterable`, `Map`, `Stream`, array or integer. The alias is used to reference the current element.",
+    example: "{#for item in items}\n  {item_count}. {item.name}\n{#else}\n  No items\n{/for}",
+    anchor: "loop_section",
+};
+
+pub const EACH: QuteDocumentation = QuteDocumentation {
+    title: "{#each}",
+    summary: "Iterates over the elements like `{#for}`. The current element is available as `it`.",
+    example: "{#each items}\n  {it.name}\n{/each}",
+    anchor: "loop_section",
+};
+
+pub const IF: QuteDocumentation = QuteDocumentation {
+    title: "{#if}",
+    summary: "Renders the content when the condition is truthy. Supports operators like `&&`, `||`, `gt`, `eq` and `{#else if}` blocks.",
+    example: "{#if item.active && item.price gt 10}\n  {item.name}\n{#else if item.active}\n  cheap\n{#else}\n  inactive\n{/if}",
+    anchor: "if_section",
+};
+
+pub const ELSE: QuteDocumentation = QuteDocumentation {
+    title: "{#else}",
+    summary: "The alternative block of `{#if}`, `{#for}`, `{#each}`, `{#when}` and `{#switch}`.",
+    example: "{#if item.active}\n  active\n{#else}\n  inactive\n{/if}",
+    anchor: "if_section",
+};
+
+pub const WHEN: QuteDocumentation = QuteDocumentation {
+    title: "{#when}",
+    summary: "Similar to a java switch. Matches the value against the `{#is}` blocks. Enum constants can be used without the enum name.",
+    example: "{#when item.status}\n  {#is ON}\n    On\n  {#is in OFF BROKEN}\n    Off\n  {#else}\n    Unknown\n{/when}",
+    anchor: "when_section",
+};
+
+pub const SWITCH: QuteDocumentation = QuteDocumentation {
+    title: "{#switch}",
+    summary: "An alias of `{#when}` that uses `{#case}` blocks.",
+    example: "{#switch item.status}\n  {#case ON}\n    On\n  {#case OFF}\n    Off\n{/switch}",
+    anchor: "when_section",
+};
+
+pub const IS: QuteDocumentation = QuteDocumentation {
+    title: "{#is}",
+    summary: "A case block of `{#when}`. Supports operators like `in`, `!in`, `gt` and `le`.",
+    example: "{#when item.status}\n  {#is in ON STARTING}\n    On\n{/when}",
+    anchor: "when_section",
+};
+
+pub const CASE: QuteDocumentation = QuteDocumentation {
+    title: "{#case}",
+    summary: "A case block of `{#switch}`.",
+    example: "{#switch item.status}\n  {#case ON}\n    On\n{/switch}",
+    anchor: "when_section",
+};
+
+pub const LET: QuteDocumentation = QuteDocumentation {
+    title: "{#let}",
+    summary: "Defines named local variables that are available inside of the section.",
+    example: "{#let name=item.name price=item.price}\n  {name}: {price}\n{/let}",
+    anchor: "let_section",
+};
+
+pub const WITH: QuteDocumentation = QuteDocumentation {
+    title: "{#with}",
+    summary:
+        "Sets the current context object. Properties of the object can be used without a prefix.",
+    example: "{#with item.parent}\n  {name}\n{/with}",
+    anchor: "with_section",
+};
+
+pub const INCLUDE: QuteDocumentation = QuteDocumentation {
+    title: "{#include}",
+    summary: "Includes another template or a fragment of a template. Blocks of the included template can be overridden.",
+    example: "{#include base}\n  {#title}My Title{/title}\n{/include}\n{#include item$detail item=item /}",
+    anchor: "include_helper",
+};
+
+pub const INSERT: QuteDocumentation = QuteDocumentation {
+    title: "{#insert}",
+    summary: "Defines a block of a template that can be overridden by templates that include it.",
+    example: "<title>{#insert title}Default Title{/}</title>",
+    anchor: "include_helper",
+};
+
+pub const FRAGMENT: QuteDocumentation = QuteDocumentation {
+    title: "{#fragment}",
+    summary: "A fragment represents a part of the template that can be treated as a separate template, i.e. rendered separately.",
+    example: "{#fragment id=item_detail}\n  <h2>{item.name}</h2>\n{/fragment}",
+    anchor: "fragments",
+};
+
+pub const CAPTURE: QuteDocumentation = QuteDocumentation {
+    title: "{#capture}",
+    summary: "Like `{#fragment}` but the content is not rendered in place. It can only be rendered as a fragment.",
+    example: "{#capture id=hidden}\n  <p>{item.name}</p>\n{/capture}",
+    anchor: "fragments",
+};
+
+pub const CACHED: QuteDocumentation = QuteDocumentation {
+    title: "{#cached}",
+    summary: "Caches the rendered content. The optional `key` parameter is used to differentiate the cached content.",
+    example: "{#cached key=item.id}\n  {item.expensiveComputation}\n{/cached}",
+    anchor: "cached_section",
+};
+
+pub const EVAL: QuteDocumentation = QuteDocumentation {
+    title: "{#eval}",
+    summary: "Parses and evaluates a template dynamically.",
+    example: "{#eval myData.template name='Mia' /}",
+    anchor: "eval_section",
+};
+
+/// Documentation for a section or section block by its name. `for` for `{#for}`.
+pub fn section_documentation(name: &str) -> Option<QuteDocumentation> {
+    match name {
+        "for" => Some(FOR),
+        "each" => Some(EACH),
+        "if" => Some(IF),
+        "else" => Some(ELSE),
+        "when" => Some(WHEN),
+        "switch" => Some(SWITCH),
+        "is" => Some(IS),
+        "case" => Some(CASE),
+        "let" => Some(LET),
+        "with" => Some(WITH),
+        "include" => Some(INCLUDE),
+        "insert" => Some(INSERT),
+        "fragment" => Some(FRAGMENT),
+        "capture" => Some(CAPTURE),
+        "cached" => Some(CACHED),
+        "eval" => Some(EVAL),
+        _ => None,
+    }
+}
+
+/// Documentation for the iteration metadata of a loop. `count` for `{item_count}`.
+pub fn iteration_metadata_documentation(name: &str) -> Option<QuteDocumentation> {
+    let (title, summary) = match name {
+        "count" => ("count", "1-based index"),
+        "index" => ("index", "zero-based index"),
+        "hasNext" => ("hasNext", "`true` if the iteration has more elements"),
+        "isLast" => ("isLast", "`true` if `hasNext == false`"),
+        "isFirst" => ("isFirst", "`true` if `count == 1`"),
+        "odd" => ("odd", "`true` if the element's count is odd"),
+        "even" => ("even", "`true` if the element's count is even"),
+        "indexParity" => (
+            "indexParity",
+            "outputs `odd` or `even` based on the count value",
+        ),
+        _ => return None,
+    };
+    Some(QuteDocumentation {
+        title,
+        summary,
+        example:
+            "{#for item in items}\n  {item_count}: {item.name}{#if item_hasNext},{/if}\n{/for}",
+        anchor: "loop_section",
+    })
+}
+
+/// Documentation for built-in virtual methods. `or` for `{item.name.or('-')}`.
+pub fn virtual_method_documentation(name: &str) -> Option<QuteDocumentation> {
+    let (title, summary, example) = match name {
+        "or" => (
+            "or",
+            "Outputs the default value if the previous part cannot be resolved or resolves to `null`.",
+            "{item.name.or('N/A')}\n{item.name ?: 'N/A'}",
+        ),
+        "ifTruthy" => (
+            "ifTruthy",
+            "Outputs the default value if the previous part is truthy.",
+            "{item.isActive.ifTruthy('active')}",
+        ),
+        "orEmpty" => (
+            "orEmpty",
+            "Outputs an empty list if the previous part cannot be resolved or resolves to `null`.",
+            "{#for pet in pets.orEmpty}{pet.name}{/for}",
+        ),
+        "raw" | "safe" => (
+            "raw",
+            "Marks the value as safe. The output is not escaped.",
+            "{paragraph.raw}",
+        ),
+        "size" => (
+            "size",
+            "Returns the number of elements of a collection or map.",
+            "{items.size}",
+        ),
+        "isEmpty" => (
+            "isEmpty",
+            "Returns `true` if the collection, map or string is empty.",
+            "{#if items.isEmpty}No items{/if}",
+        ),
+        "get" => (
+            "get",
+            "Returns the element at the index of a list or the value of the key of a map.",
+            "{items.get(0)}\n{map.get('key')}",
+        ),
+        "first" => (
+            "first",
+            "Returns the first element of a list.",
+            "{items.first}",
+        ),
+        "last" => ("last", "Returns the last element of a list.", "{items.last}"),
+        "take" => (
+            "take",
+            "Returns the first n elements of a list.",
+            "{#for item in items.take(3)}{item.name}{/for}",
+        ),
+        "takeLast" => (
+            "takeLast",
+            "Returns the last n elements of a list.",
+            "{#for item in items.takeLast(3)}{item.name}{/for}",
+        ),
+        "contains" => (
+            "contains",
+            "Returns `true` if the string or collection contains the value.",
+            "{#if item.name.contains('Quarkus')}...{/if}",
+        ),
+        "fmt" | "format" => (
+            "fmt",
+            "Formats the value with `String.format()`.",
+            "{item.price.fmt('%.2f')}",
+        ),
+        _ => return None,
+    };
+    Some(QuteDocumentation {
+        title,
+        summary,
+        example,
+        anchor: "virtual_methods",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{section_documentation, virtual_method_documentation};
+
+    #[test]
+    fn markdown_contains_link() {
+        let out = section_documentation("for").unwrap().markdown();
+        assert!(out.starts_with("**{#for}**"));
+        assert!(out
+            .ends_with("[Qute reference](https://quarkus.io/guides/qute-reference#loop_section)"));
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(section_documentation("myTag"), None);
+        assert_eq!(virtual_method_documentation("name"), None);
+    }
+}
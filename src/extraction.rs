@@ -206,7 +206,7 @@ pub fn to_lsp_position(point: Point) -> tower_lsp::lsp_types::Position {
     )
 }
 
-fn get_id_of_node<'a>(
+pub fn get_id_of_node<'a>(
     language: &Language,
     node: Node<'a>,
     content: &'a str,
@@ -291,7 +291,7 @@ fn get_element_node(node: Node<'_>) -> Result<Node<'_>, TreesitterError> {
     }
 }
 
-fn get_tree(content: &str, language: &Language) -> Result<Tree, TreesitterError> {
+pub fn get_tree(content: &str, language: &Language) -> Result<Tree, TreesitterError> {
     let mut parser = Parser::new();
     if parser.set_language(*language).is_err() {
         return Err(TreesitterError::UnableToParse);
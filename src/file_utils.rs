@@ -3,6 +3,8 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use tower_lsp::lsp_types::Url;
+
 pub fn find_files(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
     let mut buf = vec![];
     let entries = fs::read_dir(path)?;
@@ -23,3 +25,27 @@ pub fn find_files(path: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
 
     Ok(buf)
 }
+
+/// The canonical path of the part that exists, followed by the rest of the path. Deleted or
+/// not yet created files get the same form as existing ones.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    let mut rest = vec![];
+    let mut existing = path;
+    loop {
+        if let Ok(canonical) = fs::canonicalize(existing) {
+            return canonical.join(rest.into_iter().rev().collect::<PathBuf>());
+        }
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            return path.to_path_buf();
+        };
+        rest.push(name);
+        existing = parent;
+    }
+}
+
+/// The uri of the canonical path. The indexes are keyed by it.
+pub fn canonical_uri(uri: &Url) -> Url {
+    uri.to_file_path()
+        .and_then(|path| Url::from_file_path(canonical_path(&path)))
+        .unwrap_or(uri.clone())
+}
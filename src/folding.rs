@@ -0,0 +1,120 @@
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+use tree_sitter::Node;
+
+use crate::{
+    extraction::get_tree,
+    parser::qute::{self, QuteNode, Section},
+};
+
+/// Folding ranges of multi line sections, comments and html elements. The line of the end tag
+/// stays visible.
+pub fn folding_ranges(content: &str) -> Vec<FoldingRange> {
+    let mut out = vec![];
+    for node in qute::parse(content).walk() {
+        match node {
+            QuteNode::Section(section) => section_ranges(section, &mut out),
+            QuteNode::Comment(span) => push_range(
+                &mut out,
+                span.range.start.line,
+                span.range.end.line,
+                Some(FoldingRangeKind::Comment),
+            ),
+            _ => {}
+        }
+    }
+    let language = tree_sitter_html::language();
+    if let Ok(tree) = get_tree(content, &language) {
+        element_ranges(tree.root_node(), &mut out);
+    }
+    out.sort_by_key(|r| (r.start_line, r.end_line));
+    out.dedup_by_key(|r| (r.start_line, r.end_line));
+    out
+}
+
+/// Every block of a section folds on its own so `{#else}` stays visible
+fn section_ranges(section: &Section, out: &mut Vec<FoldingRange>) {
+    let Some(end_tag) = &section.end_tag else {
+        return;
+    };
+    let mut ends = section
+        .blocks
+        .iter()
+        .skip(1)
+        .map(|b| b.tag.range.start.line)
+        .chain([end_tag.range.start.line]);
+    for block in &section.blocks {
+        let Some(end) = ends.next() else {
+            return;
+        };
+        push_range(out, block.tag.range.start.line, end.saturating_sub(1), None);
+    }
+}
+
+fn element_ranges(node: Node, out: &mut Vec<FoldingRange>) {
+    if node.kind() == "element" {
+        let start = node.start_position().row as u32;
+        let end = node.end_position().row as u32;
+        push_range(out, start, end.saturating_sub(1), None);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        element_ranges(child, out);
+    }
+}
+
+/// Ranges that do not span multiple lines are ignored
+fn push_range(
+    out: &mut Vec<FoldingRange>,
+    start_line: u32,
+    end_line: u32,
+    kind: Option<FoldingRangeKind>,
+) {
+    if end_line <= start_line {
+        return;
+    }
+    out.push(FoldingRange {
+        start_line,
+        end_line,
+        kind,
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::FoldingRangeKind;
+
+    use super::folding_ranges;
+
+    #[test]
+    fn folding_ranges_basic() {
+        let content = "{!
+  items of the customer
+!}
+<ul>
+  {#for item in items}
+  <li>
+    {item.name}
+  </li>
+  {#else}
+  <li>none</li>
+  <li>at all</li>
+  {/for}
+</ul>
+{#if a}<p>a</p>{/if}";
+        let out = folding_ranges(content);
+        assert_eq!(
+            out.iter()
+                .map(|r| (r.start_line, r.end_line, r.kind.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 2, Some(FoldingRangeKind::Comment)),
+                (3, 11, None),
+                (4, 7, None),
+                (5, 6, None),
+                (8, 10, None),
+            ]
+        );
+    }
+}
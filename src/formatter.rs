@@ -0,0 +1,311 @@
+use std::path::PathBuf;
+
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+use tree_sitter::Node;
+
+use crate::{
+    extraction::get_tree,
+    parser::qute::{self, QuteDocument, QuteNode},
+};
+
+/// Elements whose content is never changed
+static PRESERVED_ELEMENTS: [&str; 3] = ["pre", "script", "style"];
+
+/// The indentation of one level for the options of the editor
+pub fn indent_unit(options: &FormattingOptions) -> String {
+    match options.insert_spaces {
+        true => " ".repeat(options.tab_size as usize),
+        false => "\t".to_string(),
+    }
+}
+
+/// Indents section bodies and html elements by one level per parent and normalises the
+/// whitespace in single line section tags like `{#include a   b=c/}`. Unparsed blocks,
+/// multi line comments and the content of `<pre>`, `<script>` and `<style>` stay untouched.
+pub fn format(content: &str, indent: &str) -> String {
+    let normalised = normalise_tags(content);
+    let lines = indent_lines(&normalised, indent);
+    normalised
+        .split('\n')
+        .zip(lines)
+        .map(|(line, formatted)| match line.ends_with('\r') {
+            true => formatted + "\r",
+            false => formatted,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The edits of every line that changes. Only lines that start in the range when a range is
+/// given.
+pub fn format_edits(content: &str, indent: &str, range: Option<Range>) -> Vec<TextEdit> {
+    let formatted = format(content, indent);
+    content
+        .split('\n')
+        .zip(formatted.split('\n'))
+        .enumerate()
+        .filter(|(i, _)| {
+            range.is_none_or(|r| r.start.line as usize <= *i && *i <= r.end.line as usize)
+        })
+        .filter(|(_, (line, formatted))| line != formatted)
+        .map(|(i, (line, formatted))| {
+            let line = line.trim_end_matches('\r');
+            let end = line.encode_utf16().count() as u32;
+            TextEdit::new(
+                Range::new(Position::new(i as u32, 0), Position::new(i as u32, end)),
+                formatted.trim_end_matches('\r').to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Formats the files in place. With `check` the files are only compared and the ones that
+/// are not formatted are printed. Returns false when a file is not formatted or can not be
+/// read.
+pub fn format_files(files: &[PathBuf], check: bool, indent: &str) -> bool {
+    let mut success = true;
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            eprintln!("Unable to read {}", file.display());
+            success = false;
+            continue;
+        };
+        let formatted = format(&content, indent);
+        if formatted == content {
+            continue;
+        }
+        if check {
+            println!("{}", file.display());
+            success = false;
+        } else if let Err(e) = std::fs::write(file, formatted) {
+            eprintln!("Unable to write {}: {e}", file.display());
+            success = false;
+        }
+    }
+    success
+}
+
+/// `{#include a   b=c/}` => `{#include a b=c /}`. Tags over multiple lines are kept.
+fn normalise_tags(content: &str) -> String {
+    let document = qute::parse(content);
+    let preserved = preserved_regions(content, &document);
+    let mut edits: Vec<(usize, usize, String)> = document
+        .sections()
+        .into_iter()
+        .filter(|s| !content[s.start_tag.start..s.start_tag.end].contains('\n'))
+        .filter(|s| {
+            !preserved
+                .iter()
+                .any(|(start, end)| *start < s.span.start && s.span.start < *end)
+        })
+        .map(|section| {
+            let mut tag = format!("{{#{}", section.name);
+            for parameter in section.parameters() {
+                tag.push(' ');
+                tag.push_str(&content[parameter.span.start..parameter.span.end]);
+            }
+            tag.push_str(if section.self_closing { " /}" } else { "}" });
+            (section.start_tag.start, section.start_tag.end, tag)
+        })
+        .collect();
+    let mut out = content.to_string();
+    edits.sort_by_key(|e| e.0);
+    for (start, end, tag) in edits.into_iter().rev() {
+        out.replace_range(start..end, &tag);
+    }
+    out
+}
+
+/// The formatted lines without the line endings
+fn indent_lines(content: &str, indent: &str) -> Vec<String> {
+    let document = qute::parse(content);
+    let preserved = preserved_regions(content, &document);
+    let mut levels = vec![];
+    for node in document.walk() {
+        let QuteNode::Section(section) = node else {
+            continue;
+        };
+        let Some(end_tag) = &section.end_tag else {
+            continue;
+        };
+        let ends = section
+            .blocks
+            .iter()
+            .skip(1)
+            .map(|b| b.tag.start)
+            .chain([end_tag.start]);
+        for (block, end) in section.blocks.iter().zip(ends) {
+            levels.push((block.tag.end, end));
+        }
+        // continuation lines of the start tag
+        levels.push((section.start_tag.start + 1, section.start_tag.end));
+    }
+    if let Ok(tree) = get_tree(content, &tree_sitter_html::language()) {
+        element_levels(tree.root_node(), &mut levels);
+    }
+    let mut out = vec![];
+    let mut line_start = 0;
+    for line in content.split('\n') {
+        let line_without_end = line.trim_end_matches('\r');
+        let is_preserved = preserved
+            .iter()
+            .any(|(start, end)| *start < line_start && line_start < *end);
+        let trimmed = line_without_end.trim();
+        if is_preserved {
+            out.push(line_without_end.to_string());
+        } else if trimmed.is_empty() {
+            out.push(String::new());
+        } else {
+            let offset = line_start + line_without_end.len() - line_without_end.trim_start().len();
+            let level = levels
+                .iter()
+                .filter(|(start, end)| *start <= offset && offset < *end)
+                .count();
+            out.push(indent.repeat(level) + trimmed);
+        }
+        line_start += line.len() + 1;
+    }
+    out
+}
+
+/// The content of elements and their continuation lines of the start tag add a level
+fn element_levels(node: Node, out: &mut Vec<(usize, usize)>) {
+    if node.kind() == "element" {
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        let start_tag = children.iter().find(|c| c.kind() == "start_tag");
+        let end_tag = children.iter().find(|c| c.kind() == "end_tag");
+        if let Some(start_tag) = start_tag {
+            out.push((start_tag.start_byte() + 1, start_tag.end_byte()));
+            let end = end_tag.map_or(node.end_byte(), |t| t.start_byte());
+            out.push((start_tag.end_byte(), end));
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        element_levels(child, out);
+    }
+}
+
+/// Byte ranges that are never changed. Unparsed blocks, comments and elements like `<pre>`.
+fn preserved_regions(content: &str, document: &QuteDocument) -> Vec<(usize, usize)> {
+    let mut out: Vec<_> = document
+        .walk()
+        .into_iter()
+        .filter_map(|node| match node {
+            QuteNode::Unparsed(span) | QuteNode::Comment(span) => Some((span.start, span.end)),
+            _ => None,
+        })
+        .collect();
+    if let Ok(tree) = get_tree(content, &tree_sitter_html::language()) {
+        preserved_elements(tree.root_node(), content, &mut out);
+    }
+    out
+}
+
+fn preserved_elements(node: Node, content: &str, out: &mut Vec<(usize, usize)>) {
+    let tag_name = node
+        .child(0)
+        .and_then(|tag| tag.named_child(0))
+        .filter(|name| name.kind() == "tag_name")
+        .and_then(|name| name.utf8_text(content.as_bytes()).ok());
+    let is_element = matches!(node.kind(), "element" | "script_element" | "style_element");
+    if is_element
+        && tag_name.is_some_and(|name| PRESERVED_ELEMENTS.contains(&name.to_lowercase().as_str()))
+    {
+        out.push((node.start_byte(), node.end_byte()));
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        preserved_elements(child, content, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+    use super::{format, format_edits};
+
+    #[test]
+    fn format_nested() {
+        let content = "{#include base}
+{#insert body}
+<ul id=\"items\">
+{#for item in items}
+      <li
+  class=\"item\">{item.name}</li>
+{#else}
+<li>none</li>
+{/for}
+</ul>
+{/insert}
+{/include}
+";
+        assert_eq!(
+            format(content, "  "),
+            "{#include base}
+  {#insert body}
+    <ul id=\"items\">
+      {#for item in items}
+        <li
+          class=\"item\">{item.name}</li>
+      {#else}
+        <li>none</li>
+      {/for}
+    </ul>
+  {/insert}
+{/include}
+"
+        );
+    }
+
+    #[test]
+    fn format_tags_and_preserved() {
+        let content = "<div>
+{#include   items$row   item=item/}
+<pre>
+  keep   {#include  a /}
+</pre>
+{|
+    {unparsed}
+|}
+  {!
+      comment
+  !}
+</div>";
+        let formatted = format(content, "    ");
+        assert_eq!(
+            formatted,
+            "<div>
+    {#include items$row item=item /}
+    <pre>
+  keep   {#include  a /}
+</pre>
+    {|
+    {unparsed}
+|}
+    {!
+      comment
+  !}
+</div>"
+        );
+        assert_eq!(format(&formatted, "    "), formatted);
+    }
+
+    #[test]
+    fn format_edits_in_range() {
+        let content = "<div>\r\n<p>a</p>\r\n<p>b</p>\r\n</div>";
+        let range = Range::new(Position::new(2, 0), Position::new(2, 3));
+        assert_eq!(
+            format_edits(content, "  ", Some(range)),
+            vec![TextEdit::new(
+                Range::new(Position::new(2, 0), Position::new(2, 8)),
+                "  <p>b</p>".to_string()
+            )]
+        );
+        assert_eq!(format_edits(content, "  ", None).len(), 2);
+    }
+}
@@ -0,0 +1,232 @@
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind};
+
+use crate::{
+    documentation::{
+        iteration_metadata_documentation, section_documentation, virtual_method_documentation,
+        QuteDocumentation, COMMENT,
+    },
+    parser::{
+        checked_template_index::CheckedTemplateIndex,
+        fragemnt::get_fragment_id,
+        qute::{
+            Expression, ExpressionPart, ExpressionPartKind, QuteDocument, QuteNode, Section, Span,
+        },
+    },
+};
+
+/// Documentation for the section, virtual method or iteration metadata below the cursor
+pub fn hover(document: &QuteDocument, offset: usize) -> Option<Hover> {
+    match document.node_at(offset)? {
+        QuteNode::Section(section) => section_hover(section, offset),
+        QuteNode::Expression(expression) => expression_hover(document, expression, offset),
+        QuteNode::Comment(span) => Some(to_hover(COMMENT, span)),
+        _ => None,
+    }
+}
+
+fn section_hover(section: &Section, offset: usize) -> Option<Hover> {
+    if let Some(block) = section.blocks.iter().find(|b| b.tag.contains(offset)) {
+        return Some(to_hover(section_documentation(&block.label)?, &block.tag));
+    }
+    let end_tag = section.end_tag.as_ref().filter(|e| e.contains(offset))?;
+    Some(to_hover(section_documentation(&section.name)?, end_tag))
+}
+
+fn expression_hover(
+    document: &QuteDocument,
+    expression: &Expression,
+    offset: usize,
+) -> Option<Hover> {
+    let part = part_at(&expression.parts, offset)?;
+    let documentation = match part.kind {
+        ExpressionPartKind::VirtualMethod(_) => virtual_method_documentation(&part.name)?,
+        ExpressionPartKind::Property => {
+            // `{item_count}` where item is the alias of a surrounding loop
+            let (alias, metadata) = part.name.rsplit_once('_')?;
+            let is_loop_alias = document
+                .sections_at(offset)
+                .into_iter()
+                .any(|s| loop_alias(s) == Some(alias));
+            if !is_loop_alias {
+                return None;
+            }
+            iteration_metadata_documentation(metadata)?
+        }
+        ExpressionPartKind::Literal => return None,
+    };
+    Some(to_hover(documentation, &part.span))
+}
+
+/// The java type of the parameter of a checked template that starts the expression below the
+/// cursor
+pub fn checked_parameter_hover(
+    document: &QuteDocument,
+    offset: usize,
+    template: &str,
+    index: &CheckedTemplateIndex,
+) -> Option<Hover> {
+    let QuteNode::Expression(expression) = document.node_at(offset)? else {
+        return None;
+    };
+    let root = root_at(expression, offset)?;
+    let fragments: Vec<String> = document
+        .sections_at(offset)
+        .into_iter()
+        .filter_map(get_fragment_id)
+        .collect();
+    let (checked_template, parameter) = index.parameter(template, &fragments, &root.name)?;
+    let mut value = format!(
+        "```java\n{} {}\n```\nParameter of `{}.{}`",
+        parameter.java_type,
+        parameter.name,
+        checked_template.class_name,
+        checked_template.method_name
+    );
+    if checked_template.require_type_safe_expressions {
+        value.push_str("\n\nEvery expression of the template is validated at build time");
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(root.span.range),
+    })
+}
+
+/// The first part of the innermost expression at the offset when the offset is on it.
+/// `item` of `{item.name}` but not of `{inject:item}`.
+fn root_at(expression: &Expression, offset: usize) -> Option<&ExpressionPart> {
+    for part in &expression.parts {
+        if let ExpressionPartKind::VirtualMethod(parameters) = &part.kind {
+            if let Some(parameter) = parameters.iter().find(|p| p.span.contains(offset)) {
+                return root_at(parameter, offset);
+            }
+        }
+    }
+    let root = expression.parts.first()?;
+    let is_root = expression.namespace.is_none()
+        && root.kind == ExpressionPartKind::Property
+        && root.span.contains(offset);
+    is_root.then_some(root)
+}
+
+/// `item` in `{#for item in items}` and `it` for `{#each items}`
+pub fn loop_alias(section: &Section) -> Option<&str> {
+    match section.name.as_str() {
+        "for" => section.parameters().first().map(|p| p.value.as_str()),
+        "each" => Some("it"),
+        _ => None,
+    }
+}
+
+/// Returns the innermost part of an expression that contains the offset
+fn part_at(parts: &[ExpressionPart], offset: usize) -> Option<&ExpressionPart> {
+    for part in parts {
+        if let ExpressionPartKind::VirtualMethod(parameters) = &part.kind {
+            if let Some(parameter) = parameters.iter().find(|p| p.span.contains(offset)) {
+                return part_at(&parameter.parts, offset);
+            }
+        }
+        if part.span.contains(offset) {
+            return Some(part);
+        }
+    }
+    None
+}
+
+fn to_hover(documentation: QuteDocumentation, span: &Span) -> Hover {
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation.markdown(),
+        }),
+        range: Some(span.range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{HoverContents, MarkupContent, Url};
+
+    use crate::{
+        documentation,
+        parser::{
+            checked_template::scan_checked_templates, checked_template_index::CheckedTemplateIndex,
+            qute,
+        },
+    };
+
+    use super::{checked_parameter_hover, hover};
+
+    fn hover_value(content: &str, needle: &str) -> Option<String> {
+        let document = qute::parse(content);
+        let offset = content.find(needle).unwrap() + 1;
+        hover(&document, offset).map(|h| match h.contents {
+            HoverContents::Markup(MarkupContent { value, .. }) => value,
+            _ => panic!("hover must be markdown"),
+        })
+    }
+
+    #[test]
+    fn hover_sections() {
+        let content = "{#for item in items}{#if item_hasNext},{#else}.{/if}{/for}";
+        assert_eq!(
+            hover_value(content, "#for"),
+            Some(documentation::FOR.markdown())
+        );
+        assert_eq!(
+            hover_value(content, "#else"),
+            Some(documentation::ELSE.markdown())
+        );
+        assert_eq!(
+            hover_value(content, "/for"),
+            Some(documentation::FOR.markdown())
+        );
+    }
+
+    #[test]
+    fn hover_expressions() {
+        let content = "{#for item in items}{item_count} {item.name.or('-')} {other_count}{/for}";
+        assert!(hover_value(content, "item_count")
+            .unwrap()
+            .starts_with("**count**"));
+        assert!(hover_value(content, "or(").unwrap().starts_with("**or**"));
+        assert_eq!(hover_value(content, "other_count"), None);
+        assert_eq!(hover_value(content, "name"), None);
+    }
+
+    #[test]
+    fn hover_checked_parameter() {
+        let java = "public class ItemResource {
+    @CheckedTemplate(requireTypeSafeExpressions = false)
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items);
+    }
+}";
+        let index = CheckedTemplateIndex::new();
+        let uri = Url::parse("file:///project/ItemResource.java").unwrap();
+        index.replace_file(uri, scan_checked_templates(java));
+        let content = "{items.size} {inject:items} {title.or(items)}";
+        let document = qute::parse(content);
+        let hover_at = |offset: usize| {
+            checked_parameter_hover(&document, offset, "ItemResource/list", &index).map(|h| match h
+                .contents
+            {
+                HoverContents::Markup(MarkupContent { value, .. }) => value,
+                _ => panic!("hover must be markdown"),
+            })
+        };
+        assert_eq!(
+            hover_at(2),
+            Some(
+                "```java\nList<Item> items\n```\nParameter of `ItemResource.Templates.list`"
+                    .to_string()
+            )
+        );
+        assert_eq!(hover_at(8), None);
+        assert_eq!(hover_at(22), None);
+        assert!(hover_at(40).is_some());
+    }
+}
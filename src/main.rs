@@ -1,32 +1,70 @@
 pub mod completion;
 mod config;
+mod diagnostics;
+mod documentation;
 mod extraction;
 mod file_utils;
+mod folding;
+mod formatter;
+mod hover;
 mod parser;
+mod project;
+mod references;
+mod rename;
+mod semantic_tokens;
+mod string_utils;
+mod symbols;
+mod text_sync;
+mod type_resolver;
+mod workspace;
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
-use config::Args;
+use config::{Args, Config};
 use dashmap::DashMap;
 use extraction::ExtractionKind;
-use parser::fragemnt::Fragment;
-use parser::route::Route;
+use parser::checked_template_index::CheckedTemplateIndex;
+use parser::java_type_index::JavaTypeIndex;
+use parser::reference_index::ReferenceIndex;
+use parser::route_index::RouteIndex;
+use parser::template_index::{IndexedTemplate, TemplateIndex};
+use project::Module;
 use ropey::Rope;
 use serde_json::Value;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use tree_sitter::Point;
+use type_resolver::TypeResolver;
 
 use crate::parser::include::QuteInclude;
+use crate::parser::qute::{LineIndex, QuteNode};
 use clap::Parser;
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    if let Some(config::Command::Format {
+        files,
+        check,
+        tab_size,
+    }) = args.command
+    {
+        let success = formatter::format_files(&files, check, &" ".repeat(tab_size));
+        std::process::exit(if success { 0 } else { 1 });
+    }
     if args.get_routes {
-        let routes = parser::route::scan_routes();
-        let Ok(strout) = serde_json::to_string(&routes) else {
+        let root = Path::new(".");
+        let config = Config::load(root);
+        let routes = project::discover_modules(root)
+            .iter()
+            .flat_map(|module| module.java_folders(&config))
+            .flat_map(|folder| parser::route::scan_routes(&folder))
+            .collect();
+        let routes = RouteIndex::from_routes(routes);
+        let Ok(strout) = serde_json::to_string(&routes.routes(None)) else {
             eprintln!("There was an error converting the data to json");
             return;
         };
@@ -39,26 +77,420 @@ async fn main() {
     let (service, socket) = LspService::new(|client| Backend {
         client,
         document_map: DashMap::new(),
-        fragment_map: DashMap::new(),
-        route_map: DashMap::new(),
+        route_index: RouteIndex::new(),
+        reference_index: ReferenceIndex::new(),
+        template_index: TemplateIndex::new(),
+        checked_template_index: CheckedTemplateIndex::new(),
+        java_type_index: JavaTypeIndex::new(),
+        modules: RwLock::new(vec![]),
+        config: RwLock::new(Config::default()),
+        settings: RwLock::new(Value::Null),
     });
     Server::new(stdin, stdout, socket).serve(service).await;
 }
 
+/// The id of the registration of the file watchers
+const WATCHER_ID: &str = "qute-lsp-watched-files";
+
 #[derive(Debug)]
 struct Backend {
     client: Client,
     document_map: DashMap<String, Rope>,
-    fragment_map: DashMap<String, Fragment>,
-    route_map: DashMap<String, Route>,
+    route_index: RouteIndex,
+    reference_index: ReferenceIndex,
+    template_index: TemplateIndex,
+    checked_template_index: CheckedTemplateIndex,
+    java_type_index: JavaTypeIndex,
+    /// The modules of the workspace. Every scan is resolved against them.
+    modules: RwLock<Vec<Module>>,
+    config: RwLock<Config>,
+    /// The latest settings of the editor. They override the configuration file.
+    settings: RwLock<Value>,
 }
 impl Backend {
+    fn modules(&self) -> Vec<Module> {
+        read_lock(&self.modules)
+    }
+
+    fn config(&self) -> Config {
+        read_lock(&self.config)
+    }
+
+    /// Reads the configuration file again and applies the settings of the editor
+    fn reload_config(&self) {
+        let root = self
+            .modules()
+            .first()
+            .map(|m| m.root.clone())
+            .unwrap_or_default();
+        let config = Config::load(&root).merge(&read_lock(&self.settings));
+        write_lock(&self.config, config);
+    }
+
+    /// Watches the java files, the configuration file and the template folders. An earlier
+    /// registration is removed first, so that the watchers follow the configured folders.
+    async fn register_watchers(&self, replace: bool) {
+        if replace {
+            let unregistration = Unregistration {
+                id: WATCHER_ID.to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+            };
+            if let Err(e) = self
+                .client
+                .unregister_capability(vec![unregistration])
+                .await
+            {
+                eprintln!("Unable to unregister file watcher: {e}");
+            }
+        }
+        let mut patterns = vec![
+            "**/*.java".to_string(),
+            format!("**/{}", config::CONFIG_FILE),
+        ];
+        patterns.extend(
+            self.config()
+                .template_roots
+                .iter()
+                .map(|root| format!("**/{}/**", root.trim_end_matches('/'))),
+        );
+        let watchers = patterns
+            .into_iter()
+            .map(|pattern| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(pattern),
+                kind: None,
+            })
+            .collect();
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        let registration = Registration {
+            id: WATCHER_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            eprintln!("Unable to register file watcher: {e}");
+        }
+    }
+
+    /// Reads the configuration again and indexes the workspace with it
+    async fn apply_config(&self) {
+        let template_roots = self.config().template_roots;
+        self.reload_config();
+        if self.config().template_roots != template_roots {
+            self.register_watchers(true).await;
+        }
+        self.index_workspace();
+    }
+
+    /// The root of the module that contains the file
+    fn module_root(&self, path: &Path) -> Option<PathBuf> {
+        let path = file_utils::canonical_path(path);
+        project::module_for_path(&self.modules(), &path).map(|module| module.root.clone())
+    }
+
+    /// The root of the module of the document
+    fn module_of(&self, uri: &Url) -> Option<PathBuf> {
+        self.module_root(&uri.to_file_path().ok()?)
+    }
+
+    /// The template folders of every module. The one of the module that contains `current`
+    /// is first.
+    fn template_folders(&self, current: Option<&Path>) -> Vec<PathBuf> {
+        let current = current.map(file_utils::canonical_path);
+        project::template_folders(&self.modules(), &self.config(), current.as_deref())
+    }
+
+    /// `folder/file` of the template. The file name when it is not in a template folder.
+    fn template_id(&self, uri: &Url) -> String {
+        let path = uri.to_file_path().unwrap_or_default();
+        parser::fragemnt::template_id(&path, &self.template_folders(None)).unwrap_or_else(|| {
+            path.file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Every template of every module
+    fn template_files(&self) -> Vec<PathBuf> {
+        let config = self.config();
+        self.template_folders(None)
+            .iter()
+            .filter_map(|folder| file_utils::find_files(folder).ok())
+            .flatten()
+            .filter(|path| config.is_template_suffix(path))
+            .collect()
+    }
+
+    /// Every attribute of the templates that calls a route of the java method at the position
+    fn route_references(&self, uri: &Url, content: &str, position: Position) -> Vec<Location> {
+        let Some(name_position) = parser::route_reference::method_name_at(content, position) else {
+            return vec![];
+        };
+        let uri = file_utils::canonical_uri(uri);
+        let routes: Vec<_> = self
+            .route_index
+            .routes(None)
+            .into_iter()
+            .filter(|r| {
+                r.implementation
+                    .as_ref()
+                    .is_some_and(|i| i.uri == uri && i.range.start == name_position)
+            })
+            .collect();
+        if routes.is_empty() {
+            return vec![];
+        }
+        let mut attribute_names = self.config().route_attributes;
+        attribute_names.push(parser::route_reference::LINK_ATTRIBUTE.to_string());
+        let mut out = vec![];
+        for path in self.template_files() {
+            let Ok(template_uri) = Url::from_file_path(&path) else {
+                continue;
+            };
+            let content = match self._get_opened_document(&template_uri) {
+                Some(document) => document.to_string(),
+                None => std::fs::read_to_string(&path).unwrap_or_default(),
+            };
+            let attributes =
+                parser::route_attribute::scan_route_attributes(&content, &attribute_names);
+            out.extend(
+                attributes
+                    .iter()
+                    .filter(|a| {
+                        routes
+                            .iter()
+                            .any(|r| parser::route_reference::references_route(r, a))
+                    })
+                    .map(|a| Location::new(template_uri.clone(), a.value_span.range)),
+            );
+        }
+        out
+    }
+
+    /// The template file of a template reference. The module of `current` is searched first.
+    fn template_path(&self, current: &Url, template: &str) -> Option<PathBuf> {
+        let suffixes = self.config().template_suffixes;
+        self.template_folders(current.to_file_path().ok().as_deref())
+            .iter()
+            .find_map(|folder| template_reverence_to_path(folder, &suffixes, template))
+    }
+
+    /// The template or the fragment that the native method of a checked template at the
+    /// position binds
+    fn checked_template_definition(
+        &self,
+        uri: &Url,
+        content: &str,
+        position: Position,
+    ) -> Option<GotoDefinitionResponse> {
+        let checked_template = parser::checked_template::scan_checked_templates(content)
+            .into_iter()
+            .find(|c| c.range.start <= position && position <= c.range.end)?;
+        let path = self.template_path(uri, &checked_template.template)?;
+        let template_uri = Url::from_file_path(&path).ok()?;
+        let range = match &checked_template.fragment {
+            Some(fragment) => {
+                let content = std::fs::read_to_string(&path).ok()?;
+                rename::fragment_definition(&parser::qute::parse(&content), fragment)
+                    .unwrap_or_default()
+            }
+            None => Range::default(),
+        };
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            template_uri,
+            range,
+        )))
+    }
+
+    /// The fragment id at the position of a template or java file with the range of the id
+    async fn fragment_at(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<(references::TemplateTarget, Range)> {
+        let content = self.get_document(uri).await?.to_string();
+        if uri.path().ends_with(".java") {
+            return rename::java_fragment_at(&content, position);
+        }
+        let index = LineIndex::new(&content);
+        let offset = index.offset(position);
+        let qute_document = parser::qute::parse(&content);
+        let current_template = self.template_id(uri);
+        rename::fragment_at(&qute_document, &index, offset, &current_template)
+    }
+
+    /// Scans the routes and fragments of every module
+    fn index_workspace(&self) {
+        let config = self.config();
+        self.route_index.clear();
+        self.reference_index.clear();
+        self.template_index.clear();
+        self.checked_template_index.clear();
+        self.java_type_index.clear();
+        let template_folders = self.template_folders(None);
+        let java_folders: Vec<PathBuf> = self
+            .modules()
+            .iter()
+            .flat_map(|module| module.java_folders(&config))
+            .collect();
+        let java_files = java_folders
+            .iter()
+            .filter_map(|folder| file_utils::find_files(folder).ok())
+            .flatten()
+            .filter(|path| path.extension().is_some_and(|e| e == "java"))
+            .collect::<Vec<_>>();
+        for path in &java_files {
+            self.index_routes(path);
+            self.checked_template_index.index_file(path);
+            self.java_type_index.index_file(path);
+        }
+        let template_files = self.template_files();
+        for path in &template_files {
+            self.index_template(path, &template_folders);
+        }
+        for path in template_files.into_iter().chain(java_files) {
+            references::index_file(&self.reference_index, &path, &template_folders);
+        }
+    }
+
     async fn on_change(&self, params: TextDocumentItem) {
         let rope = ropey::Rope::from_str(&params.text);
         self.document_map
             .insert(params.uri.to_string(), rope.clone());
     }
 
+    async fn publish_diagnostics(&self, uri: Url, version: Option<i32>) {
+        let config = self.config();
+        // java files are opened for route references. They are not qute templates.
+        if !config.is_template_suffix(Path::new(uri.path())) {
+            return;
+        }
+        let Some(content) = self._get_opened_document(&uri).map(|d| d.to_string()) else {
+            return;
+        };
+        let qute_document = parser::qute::parse(&content);
+        let current_template = self.template_id(&uri);
+        // includes and routes of the module of the template come first
+        let module = self.module_of(&uri);
+        let templates = self.template_index.templates(module.as_deref());
+        let fragments = self.template_index.fragments(module.as_deref());
+        let mut diagnostics = diagnostics::section_diagnostics(&uri, &qute_document);
+        diagnostics.extend(diagnostics::include_diagnostics(
+            &qute_document,
+            Some(&current_template),
+            &templates,
+            &fragments,
+        ));
+        let route_attributes =
+            parser::route_attribute::scan_route_attributes(&content, &config.route_attributes);
+        diagnostics.extend(diagnostics::route_diagnostics(
+            &route_attributes,
+            &self.route_index,
+        ));
+        diagnostics.extend(diagnostics::route_method_diagnostics(
+            &route_attributes,
+            &self.route_index,
+            module.as_deref(),
+        ));
+        let resolver = TypeResolver::new(
+            &content,
+            &qute_document,
+            &current_template,
+            &self.checked_template_index,
+            &self.java_type_index,
+        );
+        diagnostics.extend(diagnostics::property_diagnostics(&qute_document, &resolver));
+        let diagnostics = config.apply_severities(diagnostics);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+
+    /// Includes and routes of the open documents may become valid or invalid when other files
+    /// or the configuration change.
+    async fn publish_all_diagnostics(&self) {
+        let uris: Vec<Url> = self
+            .document_map
+            .iter()
+            .filter_map(|d| Url::parse(d.key()).ok())
+            .collect();
+        for uri in uris {
+            self.publish_diagnostics(uri, None).await;
+        }
+    }
+
+    /// Adds the template file with its fragments to the template index by its canonical uri
+    fn index_template(&self, path: &Path, template_folders: &[PathBuf]) {
+        let (Some(template), Ok(uri)) = (
+            parser::fragemnt::template_id(path, template_folders),
+            Url::from_file_path(file_utils::canonical_path(path)),
+        ) else {
+            return;
+        };
+        let fragments = parser::fragemnt::scan_template_file(path.to_path_buf(), &template);
+        let indexed = IndexedTemplate {
+            module: self.module_root(path).unwrap_or_default(),
+            template,
+            fragments,
+        };
+        self.template_index.replace_file(uri, indexed);
+    }
+
+    /// Replaces the routes of the java file. They are indexed by the canonical path like the
+    /// implementations of the routes.
+    fn index_routes(&self, path: &Path) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            eprintln!("Unable to read changed file {}", path.display());
+            return;
+        };
+        let path = file_utils::canonical_path(path);
+        let Ok(uri) = Url::from_file_path(&path) else {
+            return;
+        };
+        let module = self.module_root(&path).unwrap_or_default();
+        self.route_index.replace_file(
+            uri.as_str(),
+            &module,
+            parser::route::analyse_file(path, &content),
+        );
+    }
+
+    /// Re-analyses a changed java or template file. Entries of deleted files are removed.
+    fn reindex_file(&self, event: &FileEvent) {
+        // the indexes are keyed by the canonical path like in the initial scan
+        let uri = file_utils::canonical_uri(&event.uri);
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+        let deleted = event.typ == FileChangeType::DELETED;
+        if path.extension().is_some_and(|e| e == "java") {
+            if deleted {
+                self.route_index.remove_file(uri.as_str());
+                self.reference_index.remove_file(&uri);
+                self.checked_template_index.remove_file(&uri);
+                self.java_type_index.remove_file(&uri);
+                return;
+            }
+            references::index_file(&self.reference_index, &path, &[]);
+            self.checked_template_index.index_file(&path);
+            self.java_type_index.index_file(&path);
+            self.index_routes(&path);
+        } else if self.config().is_template_suffix(&path)
+            && self
+                .template_folders(None)
+                .iter()
+                .any(|folder| parser::fragemnt::is_template(&path, folder))
+        {
+            if deleted {
+                self.reference_index.remove_file(&uri);
+                self.template_index.remove_file(&uri);
+                return;
+            }
+            let template_folders = self.template_folders(None);
+            references::index_file(&self.reference_index, &path, &template_folders);
+            self.index_template(&path, &template_folders);
+        }
+    }
+
     fn _get_opened_document(
         &self,
         uri: &Url,
@@ -103,13 +535,49 @@ impl Backend {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        // files are indexed by their canonical path, so the modules are too
+        let root = file_utils::canonical_path(&workspace::get_root(&params));
+        write_lock(&self.modules, project::discover_modules(&root));
+        write_lock(
+            &self.settings,
+            params.initialization_options.unwrap_or_default(),
+        );
+        self.reload_config();
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                    TextDocumentSyncKind::INCREMENTAL,
                 )),
                 definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(rename::will_rename_options(&self.config())),
+                        ..Default::default()
+                    }),
+                }),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            range: Some(true),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
                 code_action_provider: Some(CodeActionProviderCapability::Options(
                     CodeActionOptions {
                         code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
@@ -118,7 +586,7 @@ impl LanguageServer for Backend {
                 )),
                 completion_provider: Some(CompletionOptions {
                     trigger_characters: Some(
-                        [' ', '{', '#', '!', '/']
+                        [' ', '{', '#', '!', '/', '.']
                             .iter()
                             .map(|i| i.to_string())
                             .collect(),
@@ -132,38 +600,58 @@ impl LanguageServer for Backend {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        let fragments = parser::fragemnt::scan_templates();
-        for fragemnt in fragments {
-            self.fragment_map.insert(fragemnt.id.clone(), fragemnt);
-        }
-        let routes = parser::route::scan_routes();
-        for route in routes {
-            self.route_map.insert(route.path.clone(), route);
-        }
+        self.register_watchers(false).await;
+        self.index_workspace();
     }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let config_changed = params
+            .changes
+            .iter()
+            .any(|e| e.uri.path().ends_with(config::CONFIG_FILE));
+        if config_changed {
+            self.apply_config().await;
+        } else {
+            for event in &params.changes {
+                self.reindex_file(event);
+            }
+        }
+        self.publish_all_diagnostics().await;
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        write_lock(&self.settings, params.settings);
+        self.apply_config().await;
+        self.publish_all_diagnostics().await;
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let version = params.text_document.version;
         self.on_change(TextDocumentItem {
-            uri: params.text_document.uri,
+            uri: uri.clone(),
             text: params.text_document.text,
-            version: params.text_document.version,
+            version,
             language_id: params.text_document.language_id,
         })
-        .await
+        .await;
+        self.publish_diagnostics(uri, Some(version)).await;
     }
 
-    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
-        self.on_change(TextDocumentItem {
-            uri: params.text_document.uri,
-            text: std::mem::take(&mut params.content_changes[0].text),
-            version: params.text_document.version,
-            language_id: "".to_owned(),
-        })
-        .await
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let version = params.text_document.version;
+        {
+            let mut document = self.document_map.entry(uri.to_string()).or_default();
+            for change in &params.content_changes {
+                text_sync::apply_change(&mut document, change);
+            }
+        }
+        self.publish_diagnostics(uri, Some(version)).await;
     }
 
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
@@ -179,24 +667,303 @@ impl LanguageServer for Backend {
             return Ok(None);
         };
         let mut out = vec![];
+        let module = self.module_of(&uri);
         let route_completion = parser::route_completion::completion(
-            &self.route_map,
+            &self.route_index,
+            module.as_deref(),
             line.as_str().unwrap_or_default(),
             position.character as usize,
+            &self.config().route_attributes,
         );
-        out.extend(completion::completion(
-            line.to_string(),
-            position.character as usize,
-        ));
+        let content = document.to_string();
+        let offset = LineIndex::new(&content).offset(position);
+        let qute_document = parser::qute::parse(&content);
+        // There is nothing to complete inside of comments and unparsed blocks
+        if !matches!(
+            qute_document.node_at(offset),
+            Some(QuteNode::Comment(_)) | Some(QuteNode::Unparsed(_))
+        ) {
+            out.extend(completion::completion(&qute_document, &content, offset));
+            let current_template = self.template_id(&uri);
+            let resolver = TypeResolver::new(
+                &content,
+                &qute_document,
+                &current_template,
+                &self.checked_template_index,
+                &self.java_type_index,
+            );
+            out.extend(completion::member_completion(&content, offset, &resolver));
+        }
         out.extend(parser::fragemnt::completion(
-            &self.fragment_map,
-            line.to_string(),
-            position.character as usize,
+            &self.template_index.fragments(module.as_deref()),
+            &qute_document,
+            &content,
+            offset,
         ));
         out.extend(route_completion);
         Ok(Some(CompletionResponse::Array(out)))
     }
 
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let Some(document) = self.get_document(&uri).await else {
+            eprintln!("Document is not opened.");
+            return Ok(None);
+        };
+        let content = document.to_string();
+        let (template, fragment) = if uri.path().ends_with(".java") {
+            let Some(target) = references::java_target(&content, position) else {
+                return Ok(Some(self.route_references(&uri, &content, position)));
+            };
+            target
+        } else {
+            let offset = LineIndex::new(&content).offset(position);
+            let qute_document = parser::qute::parse(&content);
+            let current_template = self.template_id(&uri);
+            references::template_target(&qute_document, offset, &current_template)
+        };
+        let mut out = vec![];
+        if params.context.include_declaration {
+            let template_folders = self.template_folders(uri.to_file_path().ok().as_deref());
+            let suffixes = self.config().template_suffixes;
+            if let Some(GotoDefinitionResponse::Scalar(location)) =
+                reverence_to_gotodefiniton(&template_folders, &suffixes, &template)
+            {
+                out.push(location);
+            }
+        }
+        out.extend(
+            self.reference_index
+                .locations(&template, fragment.as_deref()),
+        );
+        Ok(Some(out))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let Some(((template, _), range)) = self
+            .fragment_at(&params.text_document.uri, params.position)
+            .await
+        else {
+            return Ok(None);
+        };
+        // the definition can not be found without the template
+        if template.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(PrepareRenameResponse::Range(range)))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let new_name = params.new_name;
+        if !rename::is_valid_fragment_id(&new_name) {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "{new_name} is not a valid fragment id"
+            )));
+        }
+        let Some(((template, Some(fragment)), _)) = self.fragment_at(&uri, position).await else {
+            return Ok(None);
+        };
+        if template.is_empty() {
+            return Ok(None);
+        }
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        let mut add_edit = |uri: Url, range: Range| {
+            let edits = changes.entry(uri).or_default();
+            if !edits.iter().any(|e| e.range == range) {
+                edits.push(TextEdit::new(range, new_name.clone()));
+            }
+        };
+        if let Some(path) = self.template_path(&uri, &template) {
+            if let Ok(template_uri) = Url::from_file_path(&path) {
+                let content = match self._get_opened_document(&template_uri) {
+                    Some(document) => document.to_string(),
+                    None => std::fs::read_to_string(&path).unwrap_or_default(),
+                };
+                let qute_document = parser::qute::parse(&content);
+                if let Some(range) = rename::fragment_definition(&qute_document, &fragment) {
+                    add_edit(template_uri, range);
+                }
+            }
+        }
+        for (reference_uri, reference) in self.reference_index.find(&template, Some(&fragment)) {
+            if let Some(range) = reference.fragment_range {
+                add_edit(reference_uri, range);
+            }
+        }
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        let symbols = symbols::document_symbols(&document.to_string());
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        Ok(Some(folding::folding_ranges(&document.to_string())))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        let data = semantic_tokens::semantic_tokens(&document.to_string(), None);
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        })))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        let data = semantic_tokens::semantic_tokens(&document.to_string(), Some(params.range));
+        Ok(Some(SemanticTokensRangeResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        })))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        let indent = formatter::indent_unit(&params.options);
+        Ok(Some(formatter::format_edits(
+            &document.to_string(),
+            &indent,
+            None,
+        )))
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        if uri.path().ends_with(".java") {
+            return Ok(None);
+        }
+        let Some(document) = self.get_document(&uri).await else {
+            return Ok(None);
+        };
+        let indent = formatter::indent_unit(&params.options);
+        Ok(Some(formatter::format_edits(
+            &document.to_string(),
+            &indent,
+            Some(params.range),
+        )))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let template_folders = self.template_folders(None);
+        let mut candidates: Vec<SymbolInformation> = self
+            .route_index
+            .routes(None)
+            .iter()
+            .filter_map(symbols::route_symbol)
+            .collect();
+        candidates.extend(self.template_files().iter().filter_map(|path| {
+            let template = parser::fragemnt::template_id(path, &template_folders)?;
+            symbols::template_symbol(path, template)
+        }));
+        candidates.extend(
+            self.template_index
+                .fragments(None)
+                .iter()
+                .filter_map(symbols::fragment_symbol),
+        );
+        Ok(Some(symbols::workspace_symbols(&params.query, candidates)))
+    }
+
+    async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+        let config = self.config();
+        let template_folders = self.template_folders(None);
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for file in params.files {
+            let (Some(old), Some(new)) = (
+                Url::parse(&file.old_uri)
+                    .ok()
+                    .and_then(|u| u.to_file_path().ok()),
+                Url::parse(&file.new_uri)
+                    .ok()
+                    .and_then(|u| u.to_file_path().ok()),
+            ) else {
+                continue;
+            };
+            for (old_path, new_path) in rename::renamed_templates(&old, &new, &config) {
+                let (Some(old_template), Some(new_template)) = (
+                    parser::fragemnt::template_id(&old_path, &template_folders),
+                    parser::fragemnt::template_id(&new_path, &template_folders),
+                ) else {
+                    continue;
+                };
+                if old_template == new_template {
+                    continue;
+                }
+                for (uri, reference) in self.reference_index.find(&old_template, None) {
+                    if let Some(edit) = rename::template_rename_edit(&reference, &new_template) {
+                        changes.entry(uri).or_default().push(edit);
+                    }
+                }
+            }
+        }
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
@@ -208,32 +975,85 @@ impl LanguageServer for Backend {
             eprintln!("Document is not opened.");
             return Ok(None);
         };
+        if uri.path().ends_with(".java") {
+            let content = document.to_string();
+            return Ok(self.checked_template_definition(&uri, &content, position));
+        }
         let Some(line) = document.get_line(position.line.try_into().unwrap_or_default()) else {
             eprintln!("Unable to read the line referecned");
             return Ok(None);
         };
         if let Some(definition) = parser::route_definiton::get_definition(
-            &self.route_map,
+            &self.route_index,
+            self.module_of(&uri).as_deref(),
             line.as_str().unwrap_or_default(),
             &position,
         ) {
             return Ok(Some(definition));
         }
 
-        if let Some(include) = parser::include::parse_include(line.to_string()) {
+        let content = document.to_string();
+        let offset = LineIndex::new(&content).offset(position);
+        let qute_document = parser::qute::parse(&content);
+        if let Some(include) = parser::include::include_at(&qute_document, offset) {
+            let template_folders = self.template_folders(uri.to_file_path().ok().as_deref());
+            let suffixes = self.config().template_suffixes;
             match include {
                 QuteInclude::Basic(reference) => {
-                    return Ok(reverence_to_gotodefiniton(&reference));
+                    return Ok(reverence_to_gotodefiniton(
+                        &template_folders,
+                        &suffixes,
+                        &reference,
+                    ));
                 }
                 QuteInclude::Fragment(fragment) => {
                     let reference = fragment.template;
-                    return Ok(reverence_to_gotodefiniton(&reference));
+                    return Ok(reverence_to_gotodefiniton(
+                        &template_folders,
+                        &suffixes,
+                        &reference,
+                    ));
                 }
             }
         }
 
         Ok(None)
     }
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let params = params.text_document_position_params;
+        let uri = params.text_document.uri;
+        let position = params.position;
+        let Some(document) = self.get_document(&uri).await else {
+            eprintln!("Document is not opened.");
+            return Ok(None);
+        };
+        let Some(line) = document.get_line(position.line.try_into().unwrap_or_default()) else {
+            eprintln!("Unable to read the line referecned");
+            return Ok(None);
+        };
+        if let Some(hover) = parser::route_hover::hover(
+            &self.route_index,
+            self.module_of(&uri).as_deref(),
+            &line.to_string(),
+            &position,
+        ) {
+            return Ok(Some(hover));
+        }
+        let content = document.to_string();
+        let offset = LineIndex::new(&content).offset(position);
+        let qute_document = parser::qute::parse(&content);
+        let current_template = self.template_id(&uri);
+        if let Some(hover) = hover::checked_parameter_hover(
+            &qute_document,
+            offset,
+            &current_template,
+            &self.checked_template_index,
+        ) {
+            return Ok(Some(hover));
+        }
+        Ok(hover::hover(&qute_document, offset))
+    }
+
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
         let Some(document) = self.get_document(&params.text_document.uri).await else {
             eprintln!("Document is not opened.");
@@ -249,6 +1069,8 @@ impl LanguageServer for Backend {
             Value::Number(point.row.into()),
             Value::Number(point.column.into()),
         ]);
+        let mut out =
+            diagnostics::quick_fixes(&params.text_document.uri, &params.context.diagnostics);
         let extract_options: Vec<CodeActionOrCommand> =
             extraction::check_extract(&document.to_string(), point)
                 .iter()
@@ -271,8 +1093,9 @@ impl LanguageServer for Backend {
                 })
                 .collect();
 
-        if !extract_options.is_empty() {
-            return Ok(Some(extract_options));
+        out.extend(extract_options);
+        if !out.is_empty() {
+            return Ok(Some(out));
         }
         Ok(None)
     }
@@ -334,8 +1157,15 @@ impl LanguageServer for Backend {
     }
 }
 
-fn reverence_to_gotodefiniton(reference: &str) -> Option<GotoDefinitionResponse> {
-    let Some(path) = template_reverence_to_path(reference) else {
+fn reverence_to_gotodefiniton(
+    template_folders: &[PathBuf],
+    suffixes: &[String],
+    reference: &str,
+) -> Option<GotoDefinitionResponse> {
+    let Some(path) = template_folders
+        .iter()
+        .find_map(|folder| template_reverence_to_path(folder, suffixes, reference))
+    else {
         eprintln!("Unable to get canonicalized path");
         return None;
     };
@@ -348,7 +1178,26 @@ fn reverence_to_gotodefiniton(reference: &str) -> Option<GotoDefinitionResponse>
         Range::default(),
     )))
 }
-pub static TEMPLATE_FOLDER: &str = "./src/main/resources/templates/";
-fn template_reverence_to_path(reverence: &str) -> Option<PathBuf> {
-    std::fs::canonicalize::<PathBuf>(format!("{}{}.html", TEMPLATE_FOLDER, reverence).into()).ok()
+fn template_reverence_to_path(
+    template_folder: &Path,
+    suffixes: &[String],
+    reverence: &str,
+) -> Option<PathBuf> {
+    suffixes.iter().find_map(|suffix| {
+        std::fs::canonicalize(template_folder.join(format!("{}.{}", reverence, suffix))).ok()
+    })
+}
+
+fn read_lock<T: Clone>(lock: &RwLock<T>) -> T {
+    match lock.read() {
+        Ok(value) => value.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
+
+fn write_lock<T>(lock: &RwLock<T>, value: T) {
+    match lock.write() {
+        Ok(mut v) => *v = value,
+        Err(e) => *e.into_inner() = value,
+    }
 }
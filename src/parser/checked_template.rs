@@ -0,0 +1,229 @@
+use tower_lsp::lsp_types::Range;
+use tree_sitter::Node;
+
+use super::java_source::JavaSource;
+
+/// A static native method of a `@CheckedTemplate` class. It binds a template or a fragment of
+/// it to typed parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedTemplate {
+    /// `folder/file` like every template reference. `ItemResource/list` for a class nested in
+    /// `ItemResource`, `list` for a top level class and `mails/list` with `basePath = "mails"`.
+    pub template: String,
+    /// `row` of `list$row`
+    pub fragment: Option<String>,
+    /// `ItemResource.Templates`
+    pub class_name: String,
+    /// `org.acme` of the file
+    pub package: Option<String>,
+    pub method_name: String,
+    pub parameters: Vec<TemplateParameter>,
+    /// Expressions that do not start with a parameter fail the build. True by default.
+    pub require_type_safe_expressions: bool,
+    /// The name of the method
+    pub range: Range,
+    /// `row` of `list$row`
+    pub fragment_range: Option<Range>,
+}
+
+/// `List<Item> items`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateParameter {
+    pub name: String,
+    pub java_type: String,
+}
+
+impl CheckedTemplate {
+    pub fn parameter(&self, name: &str) -> Option<&TemplateParameter> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+
+    /// `org.acme.ItemResource.Templates`
+    pub fn qualified_class_name(&self) -> String {
+        match &self.package {
+            Some(package) => format!("{package}.{}", self.class_name),
+            None => self.class_name.clone(),
+        }
+    }
+}
+
+/// The native methods of every `@CheckedTemplate` class of a java file
+pub fn scan_checked_templates(content: &str) -> Vec<CheckedTemplate> {
+    let Some(source) = JavaSource::parse(content) else {
+        return vec![];
+    };
+    checked_templates(&source)
+}
+
+pub fn checked_templates(source: &JavaSource) -> Vec<CheckedTemplate> {
+    let mut out = vec![];
+    classes(source, source.root_node(), &mut out);
+    let package = source.package();
+    for checked_template in &mut out {
+        checked_template.package = package.clone();
+    }
+    out
+}
+
+fn classes(source: &JavaSource, node: Node, out: &mut Vec<CheckedTemplate>) {
+    if node.kind() == "class_declaration" {
+        checked_template_class(source, node, out);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        classes(source, child, out);
+    }
+}
+
+/// The templates are `EnclosingClass/method` for nested classes and `method` for top level
+/// classes unless there is a `basePath`. `method$fragment` binds a fragment.
+fn checked_template_class(source: &JavaSource, class: Node, out: &mut Vec<CheckedTemplate>) {
+    let Some(annotation) = source.annotation(class, "CheckedTemplate") else {
+        return;
+    };
+    let enclosing_class = source.enclosing_class(class);
+    let prefix = match source.annotation_value(annotation, "basePath") {
+        Some((base_path, _)) if base_path.is_empty() => String::new(),
+        Some((base_path, _)) => base_path + "/",
+        None => enclosing_class.clone().map_or(String::new(), |c| c + "/"),
+    };
+    let require_type_safe_expressions = source
+        .annotation_argument(annotation, "requireTypeSafeExpressions")
+        .is_none_or(|value| source.text(value) != "false");
+    let Some(name) = class.child_by_field_name("name") else {
+        return;
+    };
+    let class_name = match enclosing_class {
+        Some(enclosing_class) => format!("{enclosing_class}.{}", source.text(name)),
+        None => source.text(name).to_string(),
+    };
+    let Some(body) = class.child_by_field_name("body") else {
+        return;
+    };
+    let mut cursor = body.walk();
+    for method in body.children(&mut cursor) {
+        if method.kind() != "method_declaration" {
+            continue;
+        }
+        let modifiers = source.modifiers(method);
+        let is_native = modifiers.iter().any(|m| m.kind() == "native");
+        let is_static = modifiers.iter().any(|m| m.kind() == "static");
+        let Some(name) = method.child_by_field_name("name") else {
+            continue;
+        };
+        if !is_native || !is_static {
+            continue;
+        }
+        let method_name = source.text(name);
+        let span = source.span(name);
+        let (template, fragment, fragment_range) = match method_name.split_once('$') {
+            Some((template, fragment)) => {
+                let fragment_start = span.start + template.len() + 1;
+                (
+                    template,
+                    Some(fragment.to_string()),
+                    Some(source.index.span(fragment_start, span.end).range),
+                )
+            }
+            None => (method_name, None, None),
+        };
+        out.push(CheckedTemplate {
+            template: prefix.clone() + template,
+            fragment,
+            class_name: class_name.clone(),
+            package: None,
+            method_name: method_name.to_string(),
+            parameters: parameters(source, method),
+            require_type_safe_expressions,
+            range: span.range,
+            fragment_range,
+        });
+    }
+}
+
+fn parameters(source: &JavaSource, method: Node) -> Vec<TemplateParameter> {
+    let Some(parameters) = method.child_by_field_name("parameters") else {
+        return vec![];
+    };
+    let mut cursor = parameters.walk();
+    let out = parameters
+        .named_children(&mut cursor)
+        .filter(|p| p.kind() == "formal_parameter")
+        .filter_map(|p| {
+            Some(TemplateParameter {
+                name: source.text(p.child_by_field_name("name")?).to_string(),
+                java_type: source.text(p.child_by_field_name("type")?).to_string(),
+            })
+        })
+        .collect();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    use super::{scan_checked_templates, CheckedTemplate, TemplateParameter};
+
+    fn parameter(java_type: &str, name: &str) -> TemplateParameter {
+        TemplateParameter {
+            name: name.to_string(),
+            java_type: java_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn nested_checked_template() {
+        let content = "@Path(\"/items\")
+public class ItemResource {
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items, int page);
+        public static native TemplateInstance list$row(Item item);
+        public native TemplateInstance notStatic();
+    }
+}";
+        assert_eq!(
+            scan_checked_templates(content),
+            vec![
+                CheckedTemplate {
+                    template: "ItemResource/list".to_string(),
+                    fragment: None,
+                    class_name: "ItemResource.Templates".to_string(),
+                    package: None,
+                    method_name: "list".to_string(),
+                    parameters: vec![parameter("List<Item>", "items"), parameter("int", "page")],
+                    require_type_safe_expressions: true,
+                    range: Range::new(Position::new(4, 46), Position::new(4, 50)),
+                    fragment_range: None,
+                },
+                CheckedTemplate {
+                    template: "ItemResource/list".to_string(),
+                    fragment: Some("row".to_string()),
+                    class_name: "ItemResource.Templates".to_string(),
+                    package: None,
+                    method_name: "list$row".to_string(),
+                    parameters: vec![parameter("Item", "item")],
+                    require_type_safe_expressions: true,
+                    range: Range::new(Position::new(5, 46), Position::new(5, 54)),
+                    fragment_range: Some(Range::new(Position::new(5, 51), Position::new(5, 54))),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn top_level_checked_template() {
+        let content = "@CheckedTemplate(basePath = \"mails\", requireTypeSafeExpressions = false)
+public class Templates {
+    public static native MailTemplateInstance welcome(String name);
+}";
+        let out = scan_checked_templates(content);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].template, "mails/welcome");
+        assert_eq!(out[0].class_name, "Templates");
+        assert!(!out[0].require_type_safe_expressions);
+        assert_eq!(out[0].parameter("name"), Some(&parameter("String", "name")));
+    }
+}
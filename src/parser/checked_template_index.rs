@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::Url;
+
+use super::checked_template::{scan_checked_templates, CheckedTemplate, TemplateParameter};
+
+/// The checked templates of every java file grouped by the uri of the file, so that a changed
+/// file can be replaced.
+#[derive(Debug, Default)]
+pub struct CheckedTemplateIndex {
+    files: DashMap<Url, Vec<CheckedTemplate>>,
+}
+
+impl CheckedTemplateIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn replace_file(&self, uri: Url, checked_templates: Vec<CheckedTemplate>) {
+        self.files.insert(uri, checked_templates);
+    }
+
+    /// Indexes the java file by its canonical path. Does nothing when it can not be read.
+    pub fn index_file(&self, path: &Path) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let path = std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+        let Ok(uri) = Url::from_file_path(&path) else {
+            return;
+        };
+        self.replace_file(uri, scan_checked_templates(&content));
+    }
+
+    pub fn remove_file(&self, uri: &Url) {
+        self.files.remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// The native method that binds the template or the fragment of it
+    pub fn find(&self, template: &str, fragment: Option<&str>) -> Option<CheckedTemplate> {
+        self.files.iter().find_map(|file| {
+            file.value()
+                .iter()
+                .find(|c| c.template == template && c.fragment.as_deref() == fragment)
+                .cloned()
+        })
+    }
+
+    /// The parameter of the innermost fragment that declares it or of the template. The
+    /// fragments are ordered from the outermost to the innermost.
+    pub fn parameter(
+        &self,
+        template: &str,
+        fragments: &[String],
+        name: &str,
+    ) -> Option<(CheckedTemplate, TemplateParameter)> {
+        fragments
+            .iter()
+            .rev()
+            .map(|fragment| Some(fragment.as_str()))
+            .chain([None])
+            .filter_map(|fragment| self.find(template, fragment))
+            .find_map(|checked_template| {
+                let parameter = checked_template.parameter(name)?.clone();
+                Some((checked_template, parameter))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Url;
+
+    use crate::parser::checked_template::scan_checked_templates;
+
+    use super::CheckedTemplateIndex;
+
+    #[test]
+    fn parameter_of_fragment_or_template() {
+        let content = "public class ItemResource {
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items, String title);
+        public static native TemplateInstance list$row(Item item, String title);
+    }
+}";
+        let index = CheckedTemplateIndex::new();
+        let uri = Url::parse("file:///project/ItemResource.java").unwrap();
+        index.replace_file(uri, scan_checked_templates(content));
+        let parameter = |fragments: &[String], name: &str| {
+            index
+                .parameter("ItemResource/list", fragments, name)
+                .map(|(c, p)| (c.method_name, p.java_type))
+        };
+        let row = ["row".to_string()];
+        assert_eq!(
+            parameter(&row, "title"),
+            Some(("list$row".to_string(), "String".to_string()))
+        );
+        assert_eq!(
+            parameter(&row, "items"),
+            Some(("list".to_string(), "List<Item>".to_string()))
+        );
+        assert_eq!(parameter(&[], "item"), None);
+    }
+}
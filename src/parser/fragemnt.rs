@@ -1,16 +1,19 @@
-use dashmap::DashMap;
-use tower_lsp::lsp_types::CompletionItem;
+use tower_lsp::lsp_types::{CompletionItem, Range};
 
-use crate::{file_utils::find_files, TEMPLATE_FOLDER};
+use crate::file_utils::canonical_path;
+
+use super::qute::{self, QuteDocument, Section, SectionParameter};
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Fragment {
     pub id: String,
     pub source: String,
+    /// The id of the fragment in the template
+    pub range: Range,
 }
 #[derive(Debug, PartialEq)]
 pub struct Document {
@@ -18,62 +21,57 @@ pub struct Document {
     id: String,
     fragments: Vec<Fragment>,
 }
-pub fn scan_templates() -> Vec<Fragment> {
-    let path = Path::new(TEMPLATE_FOLDER);
-    if let Ok(files) = find_files(path) {
-        return files
-            .into_iter()
-            .filter_map(|p| {
-                if let Ok(con) = fs::read_to_string(p.clone()) {
-                    if let Some(filename) = p.to_str() {
-                        return Some((p.clone(), scan_fragments(con, filename.to_string())));
-                    }
-                }
-                None
-            })
-            .flat_map(|(path, fragments)| {
-                let prefix = get_fragment_prefix(path) + "$";
-                return fragments
-                    .iter()
-                    .map(|fragment| Fragment {
-                        id: prefix.clone() + &fragment.id,
-                        source: fragment.source.clone(),
-                    })
-                    .collect::<Vec<_>>();
-            })
-            .collect();
-    }
+/// The fragments of one template file with their full id. `folder/file$frag`
+pub fn scan_template_file(path: PathBuf, template: &str) -> Vec<Fragment> {
+    let Ok(content) = fs::read_to_string(&path) else {
+        return vec![];
+    };
+    let source = get_source(&path);
+    let prefix = template.to_string() + "$";
+    scan_fragments(content, source)
+        .into_iter()
+        .map(|fragment| Fragment {
+            id: prefix.clone() + &fragment.id,
+            ..fragment
+        })
+        .collect()
+}
 
-    vec![]
+/// The canonical path of a template. Used to find the fragments of a file again when it changes.
+pub fn get_source(path: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    path.to_string_lossy().to_string()
 }
 
-/// folder/file$frag
-fn get_fragment_prefix(p: PathBuf) -> String {
-    let mut p = p.clone();
-    let mut out = vec![];
-    if let Some(filename) = get_name(&p) {
-        out.push(filename);
-    }
-    if let Some(parent) = p.parent() {
-        p = parent.to_path_buf();
-    }
-    for _ in 0..5 {
-        if !p.is_dir() {
-            break;
-        }
-        if let Some(folder_name) = get_name(&p) {
-            if folder_name == "templates" {
-                break;
-            }
-            out.push("/".to_string());
-            out.push(folder_name);
-        }
+/// Whether the file is inside of the template folder
+pub fn is_template(path: &Path, template_folder: &Path) -> bool {
+    let Ok(template_folder) = fs::canonicalize(template_folder) else {
+        return false;
+    };
+    path.starts_with(template_folder)
+}
 
-        if let Some(parent) = p.parent() {
-            p = parent.to_path_buf();
-        }
-    }
-    out.into_iter().rev().collect()
+/// `folder/file` of a template. The template folder that contains the path is stripped, the
+/// innermost one when the folders are nested. The path does not have to exist, like the new
+/// path of a renamed template. None when the path is not in one of the folders.
+pub fn template_id(path: &Path, template_folders: &[PathBuf]) -> Option<String> {
+    let canonical = canonical_path(path);
+    let relative = template_folders
+        .iter()
+        .filter_map(|folder| {
+            path.strip_prefix(folder)
+                .or_else(|_| canonical.strip_prefix(canonical_path(folder)))
+                .ok()
+        })
+        .min_by_key(|relative| relative.components().count())?;
+    let mut out: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.iter())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    out.push(get_name(relative)?);
+    Some(out.join("/"))
 }
 
 fn get_name(p: &Path) -> Option<String> {
@@ -89,26 +87,65 @@ fn get_name(p: &Path) -> Option<String> {
 }
 
 pub fn scan_fragments(content: String, source: String) -> Vec<Fragment> {
-    content
-        .lines()
-        .map(|l| l.trim())
-        .filter(|l| l.contains("{#fragment"))
-        .filter_map(|l| l.split_once("{#fragment"))
-        .map(|c| c.1)
-        .filter_map(|l| l.split_once("id="))
-        .map(|c| c.1)
-        .map(|c| c.replace('}', ""))
+    qute::parse(&content)
+        .sections()
+        .into_iter()
+        .filter_map(get_fragment_id_parameter)
         .map(|id| Fragment {
-            id: id.to_string(),
+            id: id.value.clone(),
             source: source.clone(),
+            range: id.value_span.range,
         })
         .collect()
 }
 
+/// `{#fragment id=item}` or `{#fragment item}`
+pub fn get_fragment_id(section: &Section) -> Option<String> {
+    get_fragment_id_parameter(section).map(|p| p.value.clone())
+}
+
+/// The parameter that contains the id of a fragment
+pub fn get_fragment_id_parameter(section: &Section) -> Option<&SectionParameter> {
+    if section.name != "fragment" {
+        return None;
+    }
+    section
+        .parameter("id")
+        .or_else(|| section.parameters().iter().find(|p| p.name.is_none()))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::parser::fragemnt::{scan_fragments, Fragment};
+    use std::path::PathBuf;
+
+    use crate::parser::{
+        fragemnt::{completion, scan_fragments, template_id, Fragment},
+        qute,
+    };
     use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn template_id_of_configured_folder() {
+        let folders = vec![
+            PathBuf::from("/project/src/main/resources/templates"),
+            PathBuf::from("/project/src/main/resources/views"),
+        ];
+        let id = |path: &str| template_id(&PathBuf::from(path), &folders);
+        assert_eq!(
+            id("/project/src/main/resources/templates/new/folder/items.html"),
+            Some("new/folder/items".to_string())
+        );
+        assert_eq!(
+            id("/project/src/main/resources/views/templates/items.html"),
+            Some("templates/items".to_string())
+        );
+        assert_eq!(
+            id("/project/src/main/resources/views/a/b/c/d/e/f/items.txt"),
+            Some("a/b/c/d/e/f/items".to_string())
+        );
+        assert_eq!(id("/project/src/main/resources/other/items.html"), None);
+    }
 
     #[test]
     fn scan_fragments_basic() {
@@ -127,37 +164,78 @@ mod tests {
             out,
             vec![Fragment {
                 id: "item".to_string(),
-                source: String::new()
+                source: String::new(),
+                range: Range::new(Position::new(3, 18), Position::new(3, 22)),
             }]
         )
     }
-}
 
-pub fn completion(
-    fragment_map: &DashMap<String, Fragment>,
-    line: String,
-    char_pos: usize,
-) -> Vec<CompletionItem> {
-    let last_words: &str = &line[0..char_pos];
-    if last_words.contains("{#include") {
-        let optional_close = get_optional_clonse_statement(line);
-        return fragment_map
-            .iter()
-            .map(|r| {
-                CompletionItem::new_simple(
-                    r.key().to_string() + optional_close,
-                    "Fragment from project".to_string(),
-                )
-            })
-            .collect::<Vec<_>>();
+    #[test]
+    fn completion_of_include_parameter() {
+        let fragments = vec![Fragment {
+            id: "items$row".to_string(),
+            source: String::new(),
+            range: Range::default(),
+        }];
+        let labels = |content: &str, offset: usize| {
+            completion(&fragments, &qute::parse(content), content, offset)
+                .into_iter()
+                .map(|i| i.label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels("{#include ", 10), vec!["items$row /}".to_string()]);
+        assert_eq!(labels("{#include  /}", 10), vec!["items$row".to_string()]);
+        assert_eq!(
+            labels("{#include items$row /}{#include", 31),
+            Vec::<String>::new()
+        );
+        assert_eq!(labels("{#include a b", 13), Vec::<String>::new());
+        assert_eq!(labels("{! {#include  !}", 13), Vec::<String>::new());
     }
 
-    vec![]
+    #[test]
+    fn scan_fragments_multi_line_and_same_line() {
+        let content = "{#fragment id=header}<h1>Title</h1>{/fragment}{#fragment
+    id=footer}
+<footer></footer>
+{/fragment}";
+
+        let out = scan_fragments(content.to_string(), String::new());
+        assert_eq!(
+            out.into_iter().map(|f| f.id).collect::<Vec<_>>(),
+            vec!["header".to_string(), "footer".to_string()]
+        )
+    }
 }
 
-fn get_optional_clonse_statement(line: String) -> &'static str {
-    match line.contains("/}") {
-        true => "",
-        false => " /}",
+/// The fragments that can be included by the template parameter of `{#include}` at the offset.
+/// The end of the tag is inserted when the tag is not closed yet.
+pub fn completion(
+    fragments: &[Fragment],
+    document: &QuteDocument,
+    content: &str,
+    offset: usize,
+) -> Vec<CompletionItem> {
+    let Some((start, closed)) = document.tag_at(content, offset) else {
+        return vec![];
+    };
+    let Some(parameters) = content[start..offset].strip_prefix("{#include") else {
+        return vec![];
+    };
+    // only the first parameter is the template
+    if !parameters.starts_with(char::is_whitespace)
+        || parameters.trim_start().contains(char::is_whitespace)
+    {
+        return vec![];
     }
+    let optional_close = if closed { "" } else { " /}" };
+    fragments
+        .iter()
+        .map(|f| {
+            CompletionItem::new_simple(
+                f.id.clone() + optional_close,
+                "Fragment from project".to_string(),
+            )
+        })
+        .collect::<Vec<_>>()
 }
@@ -1,5 +1,7 @@
 use tree_sitter::{Parser, Tree};
 
+use super::route_completion::get_param_name;
+
 pub fn html_inline<'a>(line: &str) -> Option<(Box<Tree>, String)> {
     let line = line;
     let mut parser = Parser::new();
@@ -27,3 +29,36 @@ pub fn html_inline<'a>(line: &str) -> Option<(Box<Tree>, String)> {
     }
     return Some((Box::new(*tree), line.to_owned()));
 }
+
+/// An attribute value below the cursor of a single line
+#[derive(Debug, PartialEq)]
+pub struct InlineAttribute {
+    pub name: Option<String>,
+    pub value: String,
+    /// Character columns of the value inside of the line
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Returns the attribute value at the char position of the line
+pub fn attribute_at(line: &str, char_pos: usize) -> Option<InlineAttribute> {
+    let (tree, line) = html_inline(line)?;
+    let mut cursor = tree.walk();
+    for _i in 0..10 {
+        cursor.goto_first_child_for_point(tree_sitter::Point {
+            row: 0,
+            column: char_pos + 8,
+        });
+    }
+    let node = cursor.node();
+    if node.kind() != "attribute_value" {
+        return None;
+    }
+    let value = node.utf8_text(line.as_bytes()).ok()?;
+    Some(InlineAttribute {
+        name: get_param_name(node, &line),
+        value: value.to_string(),
+        start: node.start_position().column.saturating_sub(8),
+        end: node.end_position().column.saturating_sub(8),
+    })
+}
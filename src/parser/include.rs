@@ -1,3 +1,5 @@
+use super::qute::{QuteDocument, Section};
+
 #[derive(Debug, PartialEq)]
 pub enum QuteInclude {
     Basic(String),
@@ -9,39 +11,48 @@ pub struct QuteIncludeFragment {
     pub fragment: String,
 }
 
-/// This will return the template name for a include section
-pub fn parse_include(line: String) -> Option<QuteInclude> {
-    let Some((_, line)) = line.trim().split_once("{#include ") else {
-        // Not an include line
+/// This will return the template name for a include section whose start tag contains the offset
+pub fn include_at(document: &QuteDocument, offset: usize) -> Option<QuteInclude> {
+    document
+        .sections()
+        .into_iter()
+        .filter(|s| s.start_tag.contains(offset))
+        .find_map(include_from_section)
+}
+
+pub fn include_from_section(section: &Section) -> Option<QuteInclude> {
+    if section.name != "include" {
         return None;
-    };
-    let Some((template, _)) = line.split_once(' ') else {
-        // Incase of detail. With html params
-        let name = line.replace('}', "");
-        return Some(QuteInclude::Basic(name));
-    };
-    // Incase of fragment
-    if let Some((template, fragment)) = line.split_once('$') {
-        if let Some((fragment, _)) = fragment.split_once(' ') {
-            return Some(QuteInclude::Fragment(QuteIncludeFragment {
-                template: template.to_string(),
-                fragment: fragment.to_string(),
-            }));
-        }
     }
-
-    let name = template.to_string();
-    Some(QuteInclude::Basic(name))
+    // The template is the first parameter without a key
+    let reference = section.parameters().iter().find(|p| p.name.is_none())?;
+    match reference.value.split_once('$') {
+        Some((template, fragment)) => Some(QuteInclude::Fragment(QuteIncludeFragment {
+            template: template.to_string(),
+            fragment: fragment.to_string(),
+        })),
+        None => Some(QuteInclude::Basic(reference.value.clone())),
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::parser::include::{QuteInclude, QuteIncludeFragment};
+    use crate::parser::{
+        include::{QuteInclude, QuteIncludeFragment},
+        qute,
+    };
 
-    use super::parse_include;
+    use super::include_from_section;
     use pretty_assertions::assert_eq;
 
+    fn parse_include(line: String) -> Option<QuteInclude> {
+        qute::parse(&line)
+            .sections()
+            .into_iter()
+            .find_map(include_from_section)
+    }
+
     #[test]
     fn basic() {
         assert_eq!(
@@ -68,6 +79,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multiple_on_one_line() {
+        assert_eq!(
+            parse_include(
+                "<div>{#fragment id=a}{#include list$item /}{/fragment}</div>".to_string()
+            ),
+            Some(QuteInclude::Fragment(QuteIncludeFragment {
+                template: "list".to_string(),
+                fragment: "item".to_string()
+            }))
+        );
+    }
+
     #[test]
     fn detail() {
         assert_eq!(
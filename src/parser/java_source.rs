@@ -0,0 +1,121 @@
+use tree_sitter::{Node, Parser, Tree};
+
+use super::qute::{LineIndex, Span};
+
+/// A java file parsed with tree-sitter together with helpers to read its nodes
+pub struct JavaSource<'a> {
+    pub content: &'a str,
+    pub index: LineIndex<'a>,
+    tree: Tree,
+}
+
+impl<'a> JavaSource<'a> {
+    pub fn parse(content: &'a str) -> Option<Self> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_java::language())
+            .expect("Error loading java grammar");
+        let tree = parser.parse(content, None)?;
+        Some(Self {
+            content,
+            index: LineIndex::new(content),
+            tree,
+        })
+    }
+
+    pub fn root_node(&self) -> Node<'_> {
+        self.tree.root_node()
+    }
+
+    pub fn text(&self, node: Node) -> &'a str {
+        node.utf8_text(self.content.as_bytes()).unwrap_or_default()
+    }
+
+    /// `org.acme` of `package org.acme;`
+    pub fn package(&self) -> Option<String> {
+        let root = self.root_node();
+        let mut cursor = root.walk();
+        let package = root
+            .named_children(&mut cursor)
+            .find(|n| n.kind() == "package_declaration")
+            .and_then(|n| n.named_child(0))
+            .map(|n| self.text(n).to_string());
+        package
+    }
+
+    pub fn span(&self, node: Node) -> Span {
+        self.index.span(node.start_byte(), node.end_byte())
+    }
+
+    /// The modifiers and annotations of a declaration
+    pub fn modifiers<'t>(&self, node: Node<'t>) -> Vec<Node<'t>> {
+        let mut cursor = node.walk();
+        let Some(modifiers) = node.children(&mut cursor).find(|c| c.kind() == "modifiers") else {
+            return vec![];
+        };
+        let mut cursor = modifiers.walk();
+        let out = modifiers.children(&mut cursor).collect();
+        out
+    }
+
+    /// The annotation of a declaration with or without arguments
+    pub fn annotation<'t>(&self, node: Node<'t>, name: &str) -> Option<Node<'t>> {
+        self.modifiers(node).into_iter().find(|m| {
+            matches!(m.kind(), "annotation" | "marker_annotation")
+                && self.annotation_name(*m) == Some(name)
+        })
+    }
+
+    /// `Location` for `@io.quarkus.qute.Location("items")`
+    pub fn annotation_name(&self, annotation: Node) -> Option<&'a str> {
+        let name = annotation.child_by_field_name("name")?;
+        self.text(name).rsplit('.').next()
+    }
+
+    /// The value of an annotation argument. `value` is also used for the argument without a
+    /// key.
+    pub fn annotation_argument<'t>(&self, annotation: Node<'t>, key: &str) -> Option<Node<'t>> {
+        let arguments = annotation.child_by_field_name("arguments")?;
+        let mut cursor = arguments.walk();
+        let out = arguments.named_children(&mut cursor).find_map(|argument| {
+            if argument.kind() != "element_value_pair" {
+                return (key == "value").then_some(argument);
+            }
+            let argument_key = argument.child_by_field_name("key")?;
+            if self.text(argument_key) != key {
+                return None;
+            }
+            argument.child_by_field_name("value")
+        });
+        out
+    }
+
+    /// The string value of an annotation argument
+    pub fn annotation_value(&self, annotation: Node, key: &str) -> Option<(String, Span)> {
+        self.string_value(self.annotation_argument(annotation, key)?)
+    }
+
+    /// The content of a string literal without the quotes
+    pub fn string_value(&self, node: Node) -> Option<(String, Span)> {
+        if node.kind() != "string_literal" {
+            return None;
+        }
+        let text = self.text(node);
+        let value = text.strip_prefix('"')?.strip_suffix('"')?;
+        let start = node.start_byte() + 1;
+        Some((
+            value.to_string(),
+            self.index.span(start, start + value.len()),
+        ))
+    }
+
+    /// The name of the class that contains a nested class
+    pub fn enclosing_class(&self, class: Node) -> Option<String> {
+        let parent = class.parent()?;
+        if parent.kind() != "class_body" {
+            return None;
+        }
+        let name = parent.parent()?.child_by_field_name("name")?;
+        Some(self.text(name).to_string())
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use tree_sitter::Node;
+
+use super::{
+    checked_template::checked_templates,
+    java_source::JavaSource,
+    template_reference::{TemplateReference, TemplateReferenceKind},
+};
+
+/// Every place in a java file that references a template. `Template` fields, `@Location`,
+/// native methods of `@CheckedTemplate` classes and `getFragment("id")` calls.
+pub fn scan_java_templates(content: &str) -> Vec<TemplateReference> {
+    let Some(source) = JavaSource::parse(content) else {
+        return vec![];
+    };
+    let mut scanner = JavaTemplateScanner {
+        source: &source,
+        fields: HashMap::new(),
+        methods: HashMap::new(),
+        out: vec![],
+    };
+    scanner.declarations(source.root_node());
+    scanner.checked_templates();
+    scanner.fragment_calls(source.root_node());
+    scanner.out
+}
+
+struct JavaTemplateScanner<'s, 'a> {
+    source: &'s JavaSource<'a>,
+    /// Template fields by name with the template they reference
+    fields: HashMap<String, String>,
+    /// Native methods of checked templates by name with the template they reference
+    methods: HashMap<String, String>,
+    out: Vec<TemplateReference>,
+}
+
+impl JavaTemplateScanner<'_, '_> {
+    fn declarations(&mut self, node: Node) {
+        match node.kind() {
+            "field_declaration" => self.field(node),
+            "annotation" if self.source.annotation_name(node) == Some("Location") => {
+                self.location(node)
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.declarations(child);
+        }
+    }
+
+    /// `Template items;` references the template `items`
+    fn field(&mut self, node: Node) {
+        let Some(java_type) = node.child_by_field_name("type") else {
+            return;
+        };
+        let java_type = self.source.text(java_type);
+        if java_type != "Template" && !java_type.ends_with(".Template") {
+            return;
+        }
+        let location = self
+            .source
+            .modifiers(node)
+            .into_iter()
+            .filter(|m| {
+                m.kind() == "annotation" && self.source.annotation_name(*m) == Some("Location")
+            })
+            .find_map(|m| self.source.annotation_value(m, "value"))
+            .map(|(value, _)| location_template(&value));
+        let mut cursor = node.walk();
+        for declarator in node.children_by_field_name("declarator", &mut cursor) {
+            let Some(name) = declarator.child_by_field_name("name") else {
+                continue;
+            };
+            let name_text = self.source.text(name).to_string();
+            // fields with a location are reported with the annotation
+            if let Some(location) = &location {
+                self.fields.insert(name_text, location.clone());
+                continue;
+            }
+            self.fields.insert(name_text.clone(), name_text.clone());
+            self.out.push(TemplateReference {
+                template: name_text,
+                fragment: None,
+                kind: TemplateReferenceKind::Field,
+                range: self.source.span(name).range,
+                fragment_range: None,
+            });
+        }
+    }
+
+    /// `@Location("detail/items.html")` references the template `detail/items`
+    fn location(&mut self, node: Node) {
+        let Some((value, mut span)) = self.source.annotation_value(node, "value") else {
+            return;
+        };
+        // the range starts with the template like for includes
+        if value.starts_with('/') {
+            span = self.source.index.span(span.start + 1, span.end);
+        }
+        self.out.push(TemplateReference {
+            template: location_template(&value),
+            fragment: None,
+            kind: TemplateReferenceKind::Location,
+            range: span.range,
+            fragment_range: None,
+        });
+    }
+
+    /// The native methods of `@CheckedTemplate` classes
+    fn checked_templates(&mut self) {
+        for checked_template in checked_templates(self.source) {
+            self.methods.insert(
+                checked_template.method_name,
+                checked_template.template.clone(),
+            );
+            self.out.push(TemplateReference {
+                template: checked_template.template,
+                fragment: checked_template.fragment,
+                kind: TemplateReferenceKind::CheckedTemplate,
+                range: checked_template.range,
+                fragment_range: checked_template.fragment_range,
+            });
+        }
+    }
+
+    /// `items.getFragment("item")` where `items` is a template field or a checked template
+    fn fragment_calls(&mut self, node: Node) {
+        if node.kind() == "method_invocation" {
+            self.fragment_call(node);
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.fragment_calls(child);
+        }
+    }
+
+    fn fragment_call(&mut self, node: Node) {
+        let is_get_fragment = node
+            .child_by_field_name("name")
+            .is_some_and(|n| self.source.text(n) == "getFragment");
+        if !is_get_fragment {
+            return;
+        }
+        let Some(argument) = node
+            .child_by_field_name("arguments")
+            .and_then(|a| a.named_child(0))
+        else {
+            return;
+        };
+        let Some((fragment, span)) = self.source.string_value(argument) else {
+            return;
+        };
+        let template = match node.child_by_field_name("object") {
+            Some(object) if object.kind() == "identifier" => {
+                self.fields.get(self.source.text(object)).cloned()
+            }
+            Some(object) if object.kind() == "method_invocation" => object
+                .child_by_field_name("name")
+                .and_then(|n| self.methods.get(self.source.text(n)).cloned()),
+            _ => None,
+        };
+        // the fragment of an unknown template can not be told apart from others with its id
+        let Some(template) = template else {
+            return;
+        };
+        self.out.push(TemplateReference {
+            template,
+            fragment: Some(fragment),
+            kind: TemplateReferenceKind::GetFragment,
+            range: span.range,
+            fragment_range: Some(span.range),
+        });
+    }
+}
+
+/// `detail/items.html` => `detail/items`
+fn location_template(location: &str) -> String {
+    let location = location.trim_start_matches('/');
+    match location.rsplit_once('.') {
+        Some((template, suffix)) if !suffix.contains('/') => template.to_string(),
+        _ => location.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::parser::template_reference::TemplateReferenceKind;
+
+    use super::scan_java_templates;
+
+    #[test]
+    fn scan_java_templates_basic() {
+        let content = "package org.acme;
+
+@Path(\"/items\")
+public class ItemResource {
+    @Inject
+    Template items;
+
+    @Location(\"detail/item.html\")
+    Template detail;
+
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items);
+        public static native TemplateInstance list$row(Item item);
+    }
+
+    @GET
+    public TemplateInstance get() {
+        items.getFragment(\"row\");
+        return Templates.list(List.of()).getFragment(\"header\");
+    }
+}";
+        let out = scan_java_templates(content);
+        assert_eq!(
+            out.iter()
+                .map(|r| (r.kind.clone(), r.template.as_str(), r.fragment.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (TemplateReferenceKind::Field, "items", None),
+                (TemplateReferenceKind::Location, "detail/item", None),
+                (
+                    TemplateReferenceKind::CheckedTemplate,
+                    "ItemResource/list",
+                    None
+                ),
+                (
+                    TemplateReferenceKind::CheckedTemplate,
+                    "ItemResource/list",
+                    Some("row")
+                ),
+                (TemplateReferenceKind::GetFragment, "items", Some("row")),
+                (
+                    TemplateReferenceKind::GetFragment,
+                    "ItemResource/list",
+                    Some("header")
+                ),
+            ]
+        );
+        let row = &out[3];
+        assert_eq!(row.range.start.character, 46);
+        assert_eq!(row.fragment_range.unwrap().start.character, 51);
+    }
+
+    #[test]
+    fn checked_template_base_path() {
+        let content = "@CheckedTemplate(basePath = \"mails\")
+public class Templates {
+    public static native MailTemplateInstance welcome(String name);
+    public native TemplateInstance notStatic();
+}";
+        let out = scan_java_templates(content);
+        assert_eq!(
+            out.iter().map(|r| r.template.as_str()).collect::<Vec<_>>(),
+            vec!["mails/welcome"]
+        );
+    }
+}
@@ -0,0 +1,600 @@
+use tree_sitter::Node;
+
+use super::java_source::JavaSource;
+
+/// A class, record, interface or enum of a java file with the members a template can reach
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaType {
+    /// `Item`
+    pub name: String,
+    /// `org.acme.Item`, `org.acme.ItemResource.Row` for a nested type
+    pub qualified_name: String,
+    /// `org.acme` of the file
+    pub package: Option<String>,
+    /// `org.acme.model.Tag` and `org.acme.model.*` of the file. Static imports are left out.
+    pub imports: Vec<String>,
+    /// `T` of `Page<T>`
+    pub type_parameters: Vec<String>,
+    /// `BaseEntity` of `extends BaseEntity`
+    pub superclass: Option<String>,
+    pub interfaces: Vec<String>,
+    pub members: Vec<JavaMember>,
+    /// Static methods of the type annotated with `@TemplateExtension`
+    pub extension_methods: Vec<ExtensionMethod>,
+    /// Lombok `@Data`, `@Value` or `@Getter` on the type or a field adds getters that are not in
+    /// the source
+    pub generated_getters: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaMember {
+    /// The name in templates. `name` for the field, the record component and `getName()`.
+    pub name: String,
+    pub java_type: String,
+    pub kind: JavaMemberKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JavaMemberKind {
+    Field,
+    RecordComponent,
+    /// The name of the method. `getName` or `isActive`.
+    Getter(String),
+    /// The types of the parameters
+    Method(Vec<String>),
+}
+
+/// `static BigDecimal discounted(Item item)` adds `discounted` to `Item`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionMethod {
+    /// The type of the first parameter
+    pub receiver: String,
+    pub member: JavaMember,
+}
+
+/// The types declared in a java file including nested ones
+pub fn scan_java_types(content: &str) -> Vec<JavaType> {
+    let Some(source) = JavaSource::parse(content) else {
+        return vec![];
+    };
+    java_types(&source)
+}
+
+pub fn java_types(source: &JavaSource) -> Vec<JavaType> {
+    let mut out = vec![];
+    let root = source.root_node();
+    let package = source.package();
+    let mut cursor = root.walk();
+    let imports: Vec<String> = root
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() == "import_declaration")
+        .map(|n| source.text(n))
+        .filter_map(|i| i.strip_prefix("import")?.trim().strip_suffix(';'))
+        .filter(|i| !i.starts_with("static "))
+        .map(|i| i.split_whitespace().collect())
+        .collect();
+    types(source, root, package.as_deref(), &mut out);
+    for java_type in &mut out {
+        java_type.package = package.clone();
+        java_type.imports = imports.clone();
+    }
+    out
+}
+
+fn types(source: &JavaSource, node: Node, prefix: Option<&str>, out: &mut Vec<JavaType>) {
+    for child in body_declarations(node) {
+        if !matches!(
+            child.kind(),
+            "class_declaration"
+                | "record_declaration"
+                | "interface_declaration"
+                | "enum_declaration"
+        ) {
+            continue;
+        }
+        let Some(java_type) = java_type(source, child, prefix) else {
+            continue;
+        };
+        let qualified_name = java_type.qualified_name.clone();
+        out.push(java_type);
+        if let Some(body) = child.child_by_field_name("body") {
+            types(source, body, Some(&qualified_name), out);
+        }
+    }
+}
+
+/// The declarations of a class body. The ones of an enum follow its constants.
+fn body_declarations(body: Node) -> Vec<Node> {
+    let mut cursor = body.walk();
+    let out = body
+        .named_children(&mut cursor)
+        .flat_map(|child| {
+            if child.kind() != "enum_body_declarations" {
+                return vec![child];
+            }
+            let mut cursor = child.walk();
+            let declarations: Vec<Node> = child.named_children(&mut cursor).collect();
+            declarations
+        })
+        .collect();
+    out
+}
+
+fn java_type(source: &JavaSource, node: Node, prefix: Option<&str>) -> Option<JavaType> {
+    let name = source.text(node.child_by_field_name("name")?).to_string();
+    let qualified_name = match prefix {
+        Some(prefix) => format!("{prefix}.{name}"),
+        None => name.clone(),
+    };
+    let is_interface = node.kind() == "interface_declaration";
+    let mut members = vec![];
+    let mut extension_methods = vec![];
+    if node.kind() == "record_declaration" {
+        members.extend(record_components(source, node));
+    }
+    if node.kind() == "enum_declaration" {
+        members.extend(enum_members());
+    }
+    let mut generated_getters = has_lombok_getters(source, node);
+    let type_extension = source.annotation(node, "TemplateExtension").is_some();
+    if let Some(body) = node.child_by_field_name("body") {
+        for declaration in body_declarations(body) {
+            match declaration.kind() {
+                "field_declaration" if !is_interface => {
+                    members.extend(fields(source, declaration));
+                    generated_getters |= has_lombok_getters(source, declaration);
+                }
+                "method_declaration" => {
+                    let extension = type_extension
+                        || source
+                            .annotation(declaration, "TemplateExtension")
+                            .is_some();
+                    if extension {
+                        extension_methods.extend(extension_method(source, declaration));
+                    } else {
+                        members.extend(method(source, declaration, is_interface));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    Some(JavaType {
+        name,
+        qualified_name,
+        package: None,
+        imports: vec![],
+        type_parameters: type_parameters(source, node),
+        superclass: node
+            .child_by_field_name("superclass")
+            .and_then(|s| s.named_child(0))
+            .map(|s| source.text(s).to_string()),
+        interfaces: interfaces(source, node),
+        members,
+        extension_methods,
+        generated_getters,
+    })
+}
+
+fn type_parameters(source: &JavaSource, node: Node) -> Vec<String> {
+    let Some(parameters) = node.child_by_field_name("type_parameters") else {
+        return vec![];
+    };
+    let mut cursor = parameters.walk();
+    let out = parameters
+        .named_children(&mut cursor)
+        .filter_map(|parameter| {
+            let mut cursor = parameter.walk();
+            let name = parameter
+                .named_children(&mut cursor)
+                .find(|c| c.kind() == "type_identifier")?;
+            Some(source.text(name).to_string())
+        })
+        .collect();
+    out
+}
+
+/// `implements` of classes and records, `extends` of interfaces
+fn interfaces(source: &JavaSource, node: Node) -> Vec<String> {
+    let mut cursor = node.walk();
+    let Some(interfaces) = node
+        .named_children(&mut cursor)
+        .find(|c| matches!(c.kind(), "super_interfaces" | "extends_interfaces"))
+    else {
+        return vec![];
+    };
+    let Some(list) = interfaces.named_child(0) else {
+        return vec![];
+    };
+    let mut cursor = list.walk();
+    let out = list
+        .named_children(&mut cursor)
+        .map(|t| source.text(t).to_string())
+        .collect();
+    out
+}
+
+fn has_lombok_getters(source: &JavaSource, node: Node) -> bool {
+    ["Data", "Value", "Getter"]
+        .iter()
+        .any(|a| source.annotation(node, a).is_some())
+}
+
+/// `name()` and `ordinal()` every enum constant has
+fn enum_members() -> Vec<JavaMember> {
+    [("name", "String"), ("ordinal", "int")]
+        .into_iter()
+        .map(|(name, java_type)| JavaMember {
+            name: name.to_string(),
+            java_type: java_type.to_string(),
+            kind: JavaMemberKind::Method(vec![]),
+        })
+        .collect()
+}
+
+fn has_modifier(source: &JavaSource, node: Node, modifier: &str) -> bool {
+    source.modifiers(node).iter().any(|m| m.kind() == modifier)
+}
+
+fn record_components(source: &JavaSource, record: Node) -> Vec<JavaMember> {
+    formal_parameters(source, record)
+        .into_iter()
+        .map(|(java_type, name)| JavaMember {
+            name,
+            java_type,
+            kind: JavaMemberKind::RecordComponent,
+        })
+        .collect()
+}
+
+/// The public instance fields. `public String name, description;` declares two fields.
+fn fields(source: &JavaSource, field: Node) -> Vec<JavaMember> {
+    if !has_modifier(source, field, "public") || has_modifier(source, field, "static") {
+        return vec![];
+    }
+    let Some(java_type) = field.child_by_field_name("type") else {
+        return vec![];
+    };
+    let mut cursor = field.walk();
+    let out = field
+        .children_by_field_name("declarator", &mut cursor)
+        .filter_map(|declarator| {
+            Some(JavaMember {
+                name: source
+                    .text(declarator.child_by_field_name("name")?)
+                    .to_string(),
+                java_type: source.text(java_type).to_string(),
+                kind: JavaMemberKind::Field,
+            })
+        })
+        .collect();
+    out
+}
+
+/// Public instance methods that return a value. Methods of interfaces are public unless they
+/// are private.
+fn method(source: &JavaSource, method: Node, is_interface: bool) -> Option<JavaMember> {
+    let is_public = if is_interface {
+        !has_modifier(source, method, "private")
+    } else {
+        has_modifier(source, method, "public")
+    };
+    if !is_public || has_modifier(source, method, "static") {
+        return None;
+    }
+    let java_type = source.text(method.child_by_field_name("type")?);
+    if java_type == "void" {
+        return None;
+    }
+    let name = source.text(method.child_by_field_name("name")?);
+    let parameters: Vec<String> = formal_parameters(source, method)
+        .into_iter()
+        .map(|(java_type, _)| java_type)
+        .collect();
+    let property = parameters
+        .is_empty()
+        .then(|| getter_property(name))
+        .flatten();
+    let (member_name, kind) = match property {
+        Some(property) => (property, JavaMemberKind::Getter(name.to_string())),
+        None => (name.to_string(), JavaMemberKind::Method(parameters)),
+    };
+    Some(JavaMember {
+        name: member_name,
+        java_type: java_type.to_string(),
+        kind,
+    })
+}
+
+/// The first parameter of a static method is the object the method extends
+fn extension_method(source: &JavaSource, method: Node) -> Option<ExtensionMethod> {
+    if !has_modifier(source, method, "static") {
+        return None;
+    }
+    let mut parameters = formal_parameters(source, method).into_iter();
+    let (receiver, _) = parameters.next()?;
+    let java_type = source.text(method.child_by_field_name("type")?);
+    let name = source.text(method.child_by_field_name("name")?);
+    Some(ExtensionMethod {
+        receiver,
+        member: JavaMember {
+            name: name.to_string(),
+            java_type: java_type.to_string(),
+            kind: JavaMemberKind::Method(parameters.map(|(java_type, _)| java_type).collect()),
+        },
+    })
+}
+
+/// `name` for `getName` and `active` for `isActive`
+fn getter_property(method: &str) -> Option<String> {
+    let rest = method
+        .strip_prefix("get")
+        .or_else(|| method.strip_prefix("is"))?;
+    let mut chars = rest.chars();
+    let first = chars.next().filter(|c| c.is_uppercase())?;
+    Some(first.to_lowercase().chain(chars).collect())
+}
+
+/// The types and names of the parameters of a method or the components of a record
+fn formal_parameters(source: &JavaSource, node: Node) -> Vec<(String, String)> {
+    let Some(parameters) = node.child_by_field_name("parameters") else {
+        return vec![];
+    };
+    let mut cursor = parameters.walk();
+    let out = parameters
+        .named_children(&mut cursor)
+        .filter_map(|parameter| match parameter.kind() {
+            "formal_parameter" => Some((
+                source
+                    .text(parameter.child_by_field_name("type")?)
+                    .to_string(),
+                source
+                    .text(parameter.child_by_field_name("name")?)
+                    .to_string(),
+            )),
+            "spread_parameter" => {
+                let mut cursor = parameter.walk();
+                let mut children = parameter
+                    .named_children(&mut cursor)
+                    .filter(|c| !matches!(c.kind(), "modifiers" | "variable_declarator"));
+                let java_type = source.text(children.next()?).to_string() + "...";
+                Some((java_type, String::new()))
+            }
+            _ => None,
+        })
+        .collect();
+    out
+}
+
+/// `List` for `java.util.List<Item>` and `Item` for `Item[]`
+pub fn simple_name(java_type: &str) -> &str {
+    let raw = raw_type(java_type);
+    raw.rsplit('.').next().unwrap_or(raw)
+}
+
+/// `org.acme.Item` for `org.acme.Item<T>[]`
+pub fn raw_type(java_type: &str) -> &str {
+    let raw = java_type.split('<').next().unwrap_or_default();
+    raw.trim().trim_end_matches("[]").trim_end_matches("...")
+}
+
+/// `[String, Item]` for `Map<String, Item>`. Wildcards are replaced by their bound.
+pub fn type_arguments(java_type: &str) -> Vec<String> {
+    let (Some(open), Some(close)) = (java_type.find('<'), java_type.rfind('>')) else {
+        return vec![];
+    };
+    if close < open {
+        return vec![];
+    }
+    let mut out = vec![];
+    let mut depth = 0;
+    let mut start = open + 1;
+    for (i, c) in java_type[open + 1..close].char_indices() {
+        let i = open + 1 + i;
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(type_argument(&java_type[start..i]));
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    out.push(type_argument(&java_type[start..close]));
+    out
+}
+
+fn type_argument(argument: &str) -> String {
+    let argument = argument.trim();
+    let argument = argument
+        .strip_prefix("? extends ")
+        .or_else(|| argument.strip_prefix("? super "))
+        .unwrap_or(argument);
+    argument.trim().to_string()
+}
+
+/// The type of the elements a `{#for}` or `{#each}` section iterates
+pub fn element_type(java_type: &str) -> Option<String> {
+    let java_type = java_type.trim();
+    if let Some(component) = java_type.strip_suffix("[]") {
+        return Some(component.trim().to_string());
+    }
+    let iterable = matches!(
+        simple_name(java_type),
+        "Iterable"
+            | "Collection"
+            | "List"
+            | "ArrayList"
+            | "LinkedList"
+            | "Set"
+            | "HashSet"
+            | "LinkedHashSet"
+            | "SortedSet"
+            | "TreeSet"
+            | "Stream"
+            | "Iterator"
+    );
+    if !iterable {
+        return None;
+    }
+    let mut arguments = type_arguments(java_type);
+    (arguments.len() == 1).then(|| arguments.remove(0))
+}
+
+/// Replaces the type parameters in a member type by the arguments of the type. `List<Item>` for
+/// `List<T>` of `Page<Item>`.
+pub fn substitute(java_type: &str, parameters: &[String], arguments: &[String]) -> String {
+    if parameters.is_empty() || parameters.len() != arguments.len() {
+        return java_type.to_string();
+    }
+    map_names(java_type, |name| {
+        let i = parameters.iter().position(|p| p == name)?;
+        Some(arguments[i].clone())
+    })
+}
+
+/// Replaces the names in a type like `Map<String, List<Item>>`. Names the function returns None
+/// for are kept.
+pub fn map_names(java_type: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut word = String::new();
+    let push_word = |word: &mut String, out: &mut String| {
+        match f(word) {
+            Some(replacement) => out.push_str(&replacement),
+            None => out.push_str(word),
+        }
+        word.clear();
+    };
+    for c in java_type.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            word.push(c);
+        } else {
+            push_word(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    push_word(&mut word, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        element_type, scan_java_types, substitute, type_arguments, ExtensionMethod, JavaMember,
+        JavaMemberKind,
+    };
+
+    fn member(name: &str, java_type: &str, kind: JavaMemberKind) -> JavaMember {
+        JavaMember {
+            name: name.to_string(),
+            java_type: java_type.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn class_members() {
+        let content = "package org.acme;
+
+public class Item extends BaseEntity implements Comparable<Item> {
+    public String name;
+    private int secret;
+    public static int COUNT;
+    public List<Item> children;
+
+    public boolean isActive() { return true; }
+    public BigDecimal getPrice() { return price; }
+    public String label(String prefix) { return prefix + name; }
+    public void update() {}
+    private String hidden() { return \"\"; }
+
+    public record Row(Item item, int index) {}
+}";
+        let types = scan_java_types(content);
+        assert_eq!(
+            types
+                .iter()
+                .map(|t| t.qualified_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["org.acme.Item", "org.acme.Item.Row"]
+        );
+        let item = &types[0];
+        assert_eq!(item.superclass.as_deref(), Some("BaseEntity"));
+        assert_eq!(item.interfaces, vec!["Comparable<Item>".to_string()]);
+        assert_eq!(
+            item.members,
+            vec![
+                member("name", "String", JavaMemberKind::Field),
+                member("children", "List<Item>", JavaMemberKind::Field),
+                member(
+                    "active",
+                    "boolean",
+                    JavaMemberKind::Getter("isActive".to_string())
+                ),
+                member(
+                    "price",
+                    "BigDecimal",
+                    JavaMemberKind::Getter("getPrice".to_string())
+                ),
+                member(
+                    "label",
+                    "String",
+                    JavaMemberKind::Method(vec!["String".to_string()])
+                ),
+            ]
+        );
+        assert_eq!(
+            types[1].members,
+            vec![
+                member("item", "Item", JavaMemberKind::RecordComponent),
+                member("index", "int", JavaMemberKind::RecordComponent),
+            ]
+        );
+    }
+
+    #[test]
+    fn generics_and_extensions() {
+        let content = "public class Page<T> {
+    public List<T> items;
+}
+
+@TemplateExtension
+class Extensions {
+    static BigDecimal discounted(Item item, int percent) { return null; }
+}";
+        let types = scan_java_types(content);
+        assert_eq!(types[0].type_parameters, vec!["T".to_string()]);
+        assert_eq!(
+            types[1].extension_methods,
+            vec![ExtensionMethod {
+                receiver: "Item".to_string(),
+                member: member(
+                    "discounted",
+                    "BigDecimal",
+                    JavaMemberKind::Method(vec!["int".to_string()])
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(
+            type_arguments("Map<String, List<? extends Item>>"),
+            vec!["String".to_string(), "List<? extends Item>".to_string()]
+        );
+        assert_eq!(
+            element_type("java.util.List<? extends Item>"),
+            Some("Item".to_string())
+        );
+        assert_eq!(element_type("Item[]"), Some("Item".to_string()));
+        assert_eq!(element_type("Map<String, Item>"), None);
+        assert_eq!(
+            substitute("Map<K, List<T>>", &["T".to_string()], &["Item".to_string()]),
+            "Map<K, List<Item>>"
+        );
+    }
+}
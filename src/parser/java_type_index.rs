@@ -0,0 +1,335 @@
+use std::path::Path;
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::Url;
+
+use super::java_type::{
+    map_names, raw_type, scan_java_types, simple_name, substitute, type_arguments, JavaMember,
+    JavaMemberKind, JavaType,
+};
+
+/// Supertypes deeper than this are not followed
+const MAX_DEPTH: usize = 16;
+
+/// The java types of every java file grouped by the uri of the file, so that a changed file can
+/// be replaced.
+#[derive(Debug, Default)]
+pub struct JavaTypeIndex {
+    files: DashMap<Url, Vec<JavaType>>,
+}
+
+impl JavaTypeIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn replace_file(&self, uri: Url, java_types: Vec<JavaType>) {
+        self.files.insert(uri, java_types);
+    }
+
+    /// Indexes the java file by its canonical path. Does nothing when it can not be read.
+    pub fn index_file(&self, path: &Path) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let path = std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+        let Ok(uri) = Url::from_file_path(&path) else {
+            return;
+        };
+        self.replace_file(uri, scan_java_types(&content));
+    }
+
+    pub fn remove_file(&self, uri: &Url) {
+        self.files.remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// The type of `org.acme.Item`, `Item` or `List<Item>`. Simple names match any package and
+    /// the first qualified name wins.
+    pub fn find(&self, java_type: &str) -> Option<JavaType> {
+        self.find_from(java_type, None)
+    }
+
+    /// The type of a name used in the type `from`. A simple name prefers a nested type of
+    /// `from`, then an import and then the package of `from` over the types of other packages.
+    pub fn find_from(&self, java_type: &str, from: Option<&JavaType>) -> Option<JavaType> {
+        let raw = raw_type(java_type);
+        if raw.is_empty() {
+            return None;
+        }
+        let suffix = format!(".{raw}");
+        let candidates: Vec<JavaType> = self
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.value()
+                    .iter()
+                    .filter(|t| t.qualified_name == raw || t.qualified_name.ends_with(&suffix))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        candidates.into_iter().min_by(|a, b| {
+            (visibility(a, raw, from), &a.qualified_name)
+                .cmp(&(visibility(b, raw, from), &b.qualified_name))
+        })
+    }
+
+    /// Replaces the indexed names in a type used in `from` by their qualified names. The type
+    /// parameters of `from` are kept.
+    pub fn qualify(&self, java_type: &str, from: &JavaType) -> String {
+        map_names(java_type, |name| {
+            if name.is_empty() || from.type_parameters.iter().any(|p| p == name) {
+                return None;
+            }
+            self.find_from(name, Some(from)).map(|t| t.qualified_name)
+        })
+    }
+
+    /// Every member of the type, its supertypes and its template extensions. Type parameters are
+    /// replaced by the arguments of `java_type`. Members of a subtype hide the inherited ones.
+    pub fn members(&self, java_type: &str) -> Vec<JavaMember> {
+        let mut out = vec![];
+        self.collect_members(java_type, 0, &mut out);
+        out
+    }
+
+    fn collect_members(&self, java_type: &str, depth: usize, out: &mut Vec<JavaMember>) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let found = self.find(java_type);
+        let arguments = type_arguments(java_type);
+        if let Some(found) = &found {
+            for member in &found.members {
+                push_member(
+                    out,
+                    JavaMember {
+                        java_type: substitute(
+                            &self.qualify(&member.java_type, found),
+                            &found.type_parameters,
+                            &arguments,
+                        ),
+                        ..member.clone()
+                    },
+                );
+            }
+        }
+        for member in self.extension_methods(java_type) {
+            push_member(out, member);
+        }
+        let Some(found) = found else {
+            return;
+        };
+        for supertype in found.superclass.iter().chain(&found.interfaces) {
+            let supertype = substitute(
+                &self.qualify(supertype, &found),
+                &found.type_parameters,
+                &arguments,
+            );
+            self.collect_members(&supertype, depth + 1, out);
+        }
+    }
+
+    fn extension_methods(&self, java_type: &str) -> Vec<JavaMember> {
+        let name = simple_name(java_type);
+        self.files
+            .iter()
+            .flat_map(|file| {
+                file.value()
+                    .iter()
+                    .flat_map(|t| &t.extension_methods)
+                    .filter(|e| simple_name(&e.receiver) == name)
+                    .map(|e| e.member.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// The member of `{item.name}`. A field, a record component, a getter or a method without
+    /// parameters.
+    pub fn property(&self, java_type: &str, name: &str) -> Option<JavaMember> {
+        self.members(java_type)
+            .into_iter()
+            .find(|member| match &member.kind {
+                JavaMemberKind::Getter(method) => member.name == name || method == name,
+                JavaMemberKind::Method(parameters) => parameters.is_empty() && member.name == name,
+                _ => member.name == name,
+            })
+    }
+
+    /// The member of `{item.label('x')}`
+    pub fn method(&self, java_type: &str, name: &str) -> Option<JavaMember> {
+        self.members(java_type)
+            .into_iter()
+            .find(|member| match &member.kind {
+                JavaMemberKind::Getter(method) => method == name,
+                JavaMemberKind::Method(_) => member.name == name,
+                _ => false,
+            })
+    }
+
+    /// True when the type and all of its superclasses and interfaces are indexed and none of
+    /// them has getters generated by Lombok. Only then a missing member is known to be missing.
+    pub fn is_complete(&self, java_type: &str) -> bool {
+        self.is_complete_at(java_type, 0)
+    }
+
+    fn is_complete_at(&self, java_type: &str, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        let Some(found) = self.find(java_type) else {
+            return false;
+        };
+        if found.generated_getters {
+            return false;
+        }
+        found
+            .superclass
+            .iter()
+            .chain(&found.interfaces)
+            .all(|supertype| self.is_complete_at(&self.qualify(supertype, &found), depth + 1))
+    }
+}
+
+/// How close a candidate for a simple name is to the type that uses the name. Lower is closer.
+fn visibility(candidate: &JavaType, raw: &str, from: Option<&JavaType>) -> usize {
+    let qualified_name = candidate.qualified_name.as_str();
+    if qualified_name == raw {
+        return 0;
+    }
+    let Some(from) = from else {
+        return 4;
+    };
+    let package = qualified_name
+        .strip_suffix(raw)
+        .and_then(|p| p.strip_suffix('.'));
+    if package == Some(from.qualified_name.as_str()) {
+        1
+    } else if from.imports.iter().any(|i| i == qualified_name) {
+        2
+    } else if package.is_some()
+        && (package == from.package.as_deref()
+            || from.imports.iter().any(|i| i.strip_suffix(".*") == package))
+    {
+        3
+    } else {
+        4
+    }
+}
+
+/// A field `name` and `getName()` are both kept. A member of the same kind hides the later one.
+fn push_member(out: &mut Vec<JavaMember>, member: JavaMember) {
+    let hidden = out.iter().any(|m| {
+        m.name == member.name
+            && std::mem::discriminant(&m.kind) == std::mem::discriminant(&member.kind)
+    });
+    if !hidden {
+        out.push(member);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Url;
+
+    use crate::parser::java_type::scan_java_types;
+
+    use super::JavaTypeIndex;
+
+    #[test]
+    fn inherited_and_generic_members() {
+        let content = "package org.acme;
+public class Base<T> {
+    public T value;
+    public String getName() { return null; }
+}
+public class Item extends Base<Tag> {
+    public String name;
+    public List<Item> children;
+}
+public class Shop extends PanacheEntity {
+}
+public class Badge implements Named {
+}
+public class Label implements Titled {
+}
+public interface Titled {
+    String getTitle();
+}
+@TemplateExtension
+class Extensions {
+    static String slug(Item item) { return null; }
+}";
+        let index = JavaTypeIndex::new();
+        let uri = Url::parse("file:///project/Item.java").unwrap();
+        index.replace_file(uri, scan_java_types(content));
+        let property = |java_type: &str, name: &str| {
+            index
+                .property(java_type, name)
+                .map(|member| member.java_type)
+        };
+        assert_eq!(property("org.acme.Item", "value"), Some("Tag".to_string()));
+        assert_eq!(property("Item", "name"), Some("String".to_string()));
+        assert_eq!(property("Item", "getName"), Some("String".to_string()));
+        assert_eq!(property("Item", "slug"), Some("String".to_string()));
+        assert_eq!(property("Item", "nmae"), None);
+        assert_eq!(
+            index.method("Item", "getName").map(|m| m.name),
+            Some("name".to_string())
+        );
+        assert!(!index.is_complete("java.util.List<org.acme.Item>"));
+        assert!(index.is_complete("Item"));
+        assert!(!index.is_complete("Shop"));
+        assert!(!index.is_complete("Badge"));
+        assert!(index.is_complete("Label"));
+    }
+
+    #[test]
+    fn find_prefers_the_package_and_imports_of_the_user() {
+        let index = JavaTypeIndex::new();
+        let file = |name: &str, content: &str| {
+            index.replace_file(
+                Url::parse(&format!("file:///project/{name}.java")).unwrap(),
+                scan_java_types(content),
+            );
+        };
+        file(
+            "a/Tag",
+            "package org.a;\npublic class Tag { public String a; }",
+        );
+        file(
+            "b/Tag",
+            "package org.b;\npublic class Tag { public String b; }",
+        );
+        file(
+            "b/Item",
+            "package org.b;\npublic class Item extends Base { public Tag tag; }",
+        );
+        file("a/Base", "package org.a;\npublic class Base {}");
+        file(
+            "b/Base",
+            "package org.b;\npublic class Base { public String id; }",
+        );
+        file(
+            "c/Item",
+            "package org.c;\nimport org.a.Tag;\npublic class Item { public Tag tag; }",
+        );
+        let tag = |item: &str| index.property(item, "tag").map(|m| m.java_type);
+        assert_eq!(tag("org.b.Item"), Some("org.b.Tag".to_string()));
+        assert_eq!(tag("org.c.Item"), Some("org.a.Tag".to_string()));
+        assert_eq!(
+            index.property("org.b.Item", "id").map(|m| m.java_type),
+            Some("String".to_string())
+        );
+        assert_eq!(
+            index.find("Tag").map(|t| t.qualified_name),
+            Some("org.a.Tag".to_string())
+        );
+    }
+}
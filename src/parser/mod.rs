@@ -1,7 +1,21 @@
+pub mod checked_template;
+pub mod checked_template_index;
 pub mod commandargs;
 pub mod fragemnt;
 pub mod include;
+pub mod java_source;
+pub mod java_template;
+pub mod java_type;
+pub mod java_type_index;
+pub mod qute;
+pub mod reference_index;
 pub mod route;
+pub mod route_attribute;
 pub mod route_completion;
 pub mod route_definiton;
+pub mod route_hover;
+pub mod route_index;
+pub mod route_reference;
+pub mod template_index;
+pub mod template_reference;
 pub mod html_utils;
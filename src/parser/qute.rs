@@ -0,0 +1,1131 @@
+use std::fmt::{Display, Formatter};
+
+use tower_lsp::lsp_types::{Position, Range};
+
+/// A region of a qute document. Stores the byte offsets and the lsp range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub range: Range,
+}
+
+impl Span {
+    /// Returns true when the byte offset is inside of the span. The end is inclusive so that a
+    /// cursor placed directly after a tag still belongs to it.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+}
+
+/// Maps byte offsets to lsp positions and back. Lsp characters are counted in utf-16 code units.
+#[derive(Debug)]
+pub struct LineIndex<'a> {
+    content: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(content: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            content,
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.content.len());
+        let line = self.line_starts.partition_point(|s| *s <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.content[line_start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(line_start) = self.line_starts.get(position.line as usize) else {
+            return self.content.len();
+        };
+        let mut utf16 = 0;
+        for (i, c) in self.content[*line_start..].char_indices() {
+            if utf16 >= position.character as usize || c == '\n' {
+                return line_start + i;
+            }
+            utf16 += c.len_utf16();
+        }
+        self.content.len()
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start,
+            end,
+            range: Range::new(self.position(start), self.position(end)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuteNode {
+    Text(Span),
+    /// `{! ... !}`
+    Comment(Span),
+    /// `{| ... |}`
+    Unparsed(Span),
+    /// `{item.name}`
+    Expression(Expression),
+    /// `{@org.acme.Item item}`
+    ParameterDeclaration(ParameterDeclaration),
+    /// `{#name ...} ... {/name}`
+    Section(Section),
+}
+
+impl QuteNode {
+    pub fn span(&self) -> &Span {
+        match self {
+            QuteNode::Text(span) => span,
+            QuteNode::Comment(span) => span,
+            QuteNode::Unparsed(span) => span,
+            QuteNode::Expression(expression) => &expression.span,
+            QuteNode::ParameterDeclaration(declaration) => &declaration.span,
+            QuteNode::Section(section) => &section.span,
+        }
+    }
+}
+
+/// A word of the document together with its location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub value: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    /// For an expression tag this includes the curly braces. For nested expressions like method
+    /// parameters and section parameters it only covers the expression itself.
+    pub span: Span,
+    /// `inject`, `config`, `msg`, ...
+    pub namespace: Option<Word>,
+    pub parts: Vec<ExpressionPart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionPart {
+    pub name: String,
+    pub span: Span,
+    pub kind: ExpressionPartKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionPartKind {
+    /// `name` in `item.name`
+    Property,
+    /// `or('default')` in `item.name.or('default')`. Infix notation `item.name or 'default'` is
+    /// also represented as a virtual method.
+    VirtualMethod(Vec<Expression>),
+    /// `'text'`, `10`, `true`, `null`
+    Literal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterDeclaration {
+    pub span: Span,
+    pub java_type: Word,
+    pub name: Word,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: String,
+    pub name_span: Span,
+    /// `{#name ...}`
+    pub start_tag: Span,
+    /// `{/name}` or `{/}`. None when the section is self closing or was never closed.
+    pub end_tag: Option<Span>,
+    pub self_closing: bool,
+    /// The first block is the main block of the section. Further blocks are started by tags like
+    /// `{#else}`, `{#is}` or `{#case}`.
+    pub blocks: Vec<SectionBlock>,
+    /// From the start of the start tag to the end of the end tag
+    pub span: Span,
+}
+
+impl Section {
+    pub fn parameters(&self) -> &[SectionParameter] {
+        match self.blocks.first() {
+            Some(block) => &block.parameters,
+            None => &[],
+        }
+    }
+
+    /// Returns the parameter with the given key. `id` in `{#fragment id=item}`
+    pub fn parameter(&self, name: &str) -> Option<&SectionParameter> {
+        self.parameters()
+            .iter()
+            .find(|p| p.name.as_deref() == Some(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionBlock {
+    pub label: String,
+    /// The tag that started this block
+    pub tag: Span,
+    pub parameters: Vec<SectionParameter>,
+    pub children: Vec<QuteNode>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionParameter {
+    /// `limit` in `limit=10`
+    pub name: Option<String>,
+    pub value: String,
+    /// The whole parameter `limit=10`
+    pub span: Span,
+    pub value_span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    /// The start tag of the section the error belongs to
+    pub related: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    UnclosedSection(String),
+    StrayEndTag(String),
+    MismatchedEndTag { expected: String, found: String },
+    UnexpectedSectionBlock(String),
+    UnterminatedTag,
+    UnterminatedComment,
+    UnterminatedUnparsed,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            ParseErrorKind::UnclosedSection(name) => {
+                format!("Section {{#{name}}} is never closed. Expected {{/{name}}}")
+            }
+            ParseErrorKind::StrayEndTag(name) => {
+                format!("End tag {{/{name}}} has no matching section")
+            }
+            ParseErrorKind::MismatchedEndTag { expected, found } => {
+                format!("End tag {{/{found}}} does not match section {{#{expected}}}")
+            }
+            ParseErrorKind::UnexpectedSectionBlock(label) => {
+                format!("{{#{label}}} is not allowed here")
+            }
+            ParseErrorKind::UnterminatedTag => "Tag is never closed. Expected }".to_string(),
+            ParseErrorKind::UnterminatedComment => {
+                "Comment is never closed. Expected !}".to_string()
+            }
+            ParseErrorKind::UnterminatedUnparsed => {
+                "Unparsed block is never closed. Expected |}".to_string()
+            }
+        };
+        write!(f, "{message}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QuteDocument {
+    pub nodes: Vec<QuteNode>,
+    pub errors: Vec<ParseError>,
+}
+
+impl QuteDocument {
+    /// All nodes of the document in document order. Parents come before their children.
+    pub fn walk(&self) -> Vec<&QuteNode> {
+        let mut out = vec![];
+        walk_nodes(&self.nodes, &mut out);
+        out
+    }
+
+    pub fn sections(&self) -> Vec<&Section> {
+        self.walk()
+            .into_iter()
+            .filter_map(|n| match n {
+                QuteNode::Section(section) => Some(section),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the innermost node that contains the offset
+    pub fn node_at(&self, offset: usize) -> Option<&QuteNode> {
+        let mut nodes = &self.nodes;
+        let mut out = None;
+        loop {
+            // text touching a tag should not hide the tag
+            let node = nodes
+                .iter()
+                .filter(|n| n.span().contains(offset))
+                .find(|n| !matches!(n, QuteNode::Text(_)))
+                .or_else(|| nodes.iter().find(|n| n.span().contains(offset)));
+            let Some(node) = node else {
+                return out;
+            };
+            out = Some(node);
+            let QuteNode::Section(section) = node else {
+                return out;
+            };
+            if section.blocks.iter().any(|b| b.tag.contains(offset))
+                || section.end_tag.as_ref().is_some_and(|e| e.contains(offset))
+            {
+                return out;
+            }
+            let Some(block) = section
+                .blocks
+                .iter()
+                .find(|b| b.children.iter().any(|c| c.span().contains(offset)))
+            else {
+                return out;
+            };
+            nodes = &block.children;
+        }
+    }
+
+    /// The start of the tag at the offset and whether the tag is closed. A tag that is still
+    /// being typed like `{#fo` is text until its `}` is written. None in comments and unparsed
+    /// blocks.
+    pub fn tag_at(&self, content: &str, offset: usize) -> Option<(usize, bool)> {
+        match self.node_at(offset)? {
+            QuteNode::Text(span) => {
+                let start = span.start + content.get(span.start..offset)?.rfind('{')?;
+                Some((start, false))
+            }
+            QuteNode::Section(section) => {
+                let tag = section
+                    .blocks
+                    .iter()
+                    .map(|b| &b.tag)
+                    .chain(&section.end_tag)
+                    .find(|t| t.contains(offset))?;
+                Some((tag.start, true))
+            }
+            QuteNode::Expression(expression) => Some((expression.span.start, true)),
+            QuteNode::ParameterDeclaration(declaration) => Some((declaration.span.start, true)),
+            QuteNode::Comment(_) | QuteNode::Unparsed(_) => None,
+        }
+    }
+
+    /// The sections that surround the offset. The outermost section comes first.
+    pub fn sections_at(&self, offset: usize) -> Vec<&Section> {
+        self.sections()
+            .into_iter()
+            .filter(|s| s.span.contains(offset))
+            .collect()
+    }
+}
+
+fn walk_nodes<'a>(nodes: &'a [QuteNode], out: &mut Vec<&'a QuteNode>) {
+    for node in nodes {
+        out.push(node);
+        if let QuteNode::Section(section) = node {
+            for block in &section.blocks {
+                walk_nodes(&block.children, out);
+            }
+        }
+    }
+}
+
+/// Returns true when a section with the name `section` can be split into blocks with `label`
+fn is_block_label(section: &str, label: &str) -> bool {
+    matches!(
+        (section, label),
+        ("if", "else")
+            | ("for", "else")
+            | ("each", "else")
+            | ("when", "is")
+            | ("when", "else")
+            | ("switch", "case")
+            | ("switch", "is")
+            | ("switch", "else")
+    )
+}
+
+struct QuteParser<'a> {
+    content: &'a str,
+    index: LineIndex<'a>,
+    /// Sections that are not closed yet
+    stack: Vec<Section>,
+    nodes: Vec<QuteNode>,
+    errors: Vec<ParseError>,
+    /// Start of the text that is not added yet
+    text_start: usize,
+}
+
+/// Parses a whole qute template
+pub fn parse(content: &str) -> QuteDocument {
+    let mut parser = QuteParser {
+        content,
+        index: LineIndex::new(content),
+        stack: vec![],
+        nodes: vec![],
+        errors: vec![],
+        text_start: 0,
+    };
+    parser.parse();
+    QuteDocument {
+        nodes: parser.nodes,
+        errors: parser.errors,
+    }
+}
+
+impl<'a> QuteParser<'a> {
+    fn parse(&mut self) {
+        let bytes = self.content.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'{' || (i > 0 && bytes[i - 1] == b'\\') {
+                i += 1;
+                continue;
+            }
+            let next = bytes.get(i + 1).copied();
+            let end = match next {
+                // `{/* */}` of javascript is text like in qute
+                Some(b'/') if bytes.get(i + 2) == Some(&b'*') => None,
+                Some(b'!') => self.comment(i),
+                Some(b'|') => self.unparsed(i),
+                Some(b'#') | Some(b'/') | Some(b'@') => self.tag(i),
+                Some(c) if !c.is_ascii_whitespace() && c != b'{' && c != b'}' => self.tag(i),
+                _ => None,
+            };
+            let Some((end, node)) = end else {
+                i += 1;
+                continue;
+            };
+            self.flush_text(i);
+            if let Some(node) = node {
+                self.push(node);
+            }
+            i = end;
+            self.text_start = end;
+        }
+        self.flush_text(bytes.len());
+        while let Some(open) = self.stack.pop() {
+            self.unclosed(open);
+        }
+    }
+
+    /// Adds the text before the tag at `end`. Has to happen before the tag opens or closes a
+    /// section so that the text ends up in the right block.
+    fn flush_text(&mut self, end: usize) {
+        if self.text_start < end {
+            let span = self.index.span(self.text_start, end);
+            self.push(QuteNode::Text(span));
+        }
+        self.text_start = end;
+    }
+
+    fn push(&mut self, node: QuteNode) {
+        match self.stack.last_mut() {
+            Some(open) => {
+                if let Some(block) = open.blocks.last_mut() {
+                    block.children.push(node);
+                }
+            }
+            None => self.nodes.push(node),
+        }
+    }
+
+    /// Closes a section and adds it to its parent
+    fn finish(&mut self, mut section: Section, end_tag: Option<Span>) {
+        let end = match &end_tag {
+            Some(end_tag) => end_tag.end,
+            None => section
+                .blocks
+                .last()
+                .and_then(|b| b.children.last())
+                .map(|c| c.span().end)
+                .unwrap_or(section.start_tag.end),
+        };
+        section.span = self.index.span(section.start_tag.start, end);
+        section.end_tag = end_tag;
+        self.push(QuteNode::Section(section));
+    }
+
+    fn unclosed(&mut self, section: Section) {
+        self.errors.push(ParseError {
+            kind: ParseErrorKind::UnclosedSection(section.name.clone()),
+            span: section.start_tag.clone(),
+            related: None,
+        });
+        self.finish(section, None);
+    }
+
+    fn comment(&mut self, start: usize) -> Option<(usize, Option<QuteNode>)> {
+        let end = match self.content[start + 2..].find("!}") {
+            Some(end) => start + 2 + end + 2,
+            None => {
+                let span = self.index.span(start, self.content.len());
+                self.errors.push(ParseError {
+                    kind: ParseErrorKind::UnterminatedComment,
+                    span,
+                    related: None,
+                });
+                self.content.len()
+            }
+        };
+        Some((end, Some(QuteNode::Comment(self.index.span(start, end)))))
+    }
+
+    fn unparsed(&mut self, start: usize) -> Option<(usize, Option<QuteNode>)> {
+        let end = match self.content[start + 2..].find("|}") {
+            Some(end) => start + 2 + end + 2,
+            None => {
+                let span = self.index.span(start, self.content.len());
+                self.errors.push(ParseError {
+                    kind: ParseErrorKind::UnterminatedUnparsed,
+                    span,
+                    related: None,
+                });
+                self.content.len()
+            }
+        };
+        Some((end, Some(QuteNode::Unparsed(self.index.span(start, end)))))
+    }
+
+    fn tag(&mut self, start: usize) -> Option<(usize, Option<QuteNode>)> {
+        let kind = self.content.as_bytes()[start + 1];
+        let Some(close) = find_tag_end(self.content, start + 1) else {
+            // something like `{foo` is just text. The error ends with the line, the tag is
+            // probably still being typed.
+            if matches!(kind, b'#' | b'/') {
+                let line_end = self.content[start..]
+                    .find(['\r', '\n'])
+                    .map_or(self.content.len(), |end| start + end);
+                let span = self.index.span(start, line_end);
+                self.errors.push(ParseError {
+                    kind: ParseErrorKind::UnterminatedTag,
+                    span,
+                    related: None,
+                });
+            }
+            return None;
+        };
+        let end = close + 1;
+        self.flush_text(start);
+        let node = match kind {
+            b'#' => {
+                self.section_start(start, end);
+                None
+            }
+            b'/' => {
+                self.section_end(start, end);
+                None
+            }
+            b'@' => self
+                .parameter_declaration(start, end)
+                .map(QuteNode::ParameterDeclaration),
+            _ => Some(QuteNode::Expression(Expression {
+                span: self.index.span(start, end),
+                ..parse_expression(&self.index, start + 1, close)
+            })),
+        };
+        Some((end, node))
+    }
+
+    fn section_start(&mut self, start: usize, end: usize) {
+        let inner_start = start + 2;
+        let mut inner_end = end - 1;
+        let self_closing = self.content[inner_start..inner_end]
+            .trim_end()
+            .ends_with('/');
+        if self_closing {
+            inner_end = inner_start + self.content[inner_start..inner_end].trim_end().len() - 1;
+        }
+        let inner = &self.content[inner_start..inner_end];
+        let name_len = inner
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(inner.len());
+        let name = inner[..name_len].to_string();
+        let name_span = self.index.span(inner_start, inner_start + name_len);
+        let tag = self.index.span(start, end);
+        let parameters = parse_section_parameters(&self.index, inner_start + name_len, inner_end);
+
+        if let Some(open) = self.stack.last_mut() {
+            if is_block_label(&open.name, &name) {
+                open.blocks.push(SectionBlock {
+                    label: name,
+                    tag,
+                    parameters,
+                    children: vec![],
+                });
+                return;
+            }
+        }
+        if matches!(name.as_str(), "else" | "is" | "case") {
+            self.errors.push(ParseError {
+                kind: ParseErrorKind::UnexpectedSectionBlock(name),
+                span: tag,
+                related: self.stack.last().map(|o| o.start_tag.clone()),
+            });
+            return;
+        }
+
+        let section = Section {
+            name: name.clone(),
+            name_span,
+            start_tag: tag.clone(),
+            end_tag: None,
+            self_closing,
+            blocks: vec![SectionBlock {
+                label: name,
+                tag: tag.clone(),
+                parameters,
+                children: vec![],
+            }],
+            span: tag,
+        };
+        if self_closing {
+            self.finish(section, None);
+        } else {
+            self.stack.push(section);
+        }
+    }
+
+    fn section_end(&mut self, start: usize, end: usize) {
+        let name = self.content[start + 2..end - 1].trim().to_string();
+        let tag = self.index.span(start, end);
+        let Some(open) = self.stack.last() else {
+            self.errors.push(ParseError {
+                kind: ParseErrorKind::StrayEndTag(name),
+                span: tag,
+                related: None,
+            });
+            return;
+        };
+        if name.is_empty() || open.name == name {
+            if let Some(open) = self.stack.pop() {
+                self.finish(open, Some(tag));
+            }
+            return;
+        }
+        if self.stack.iter().any(|o| o.name == name) {
+            // every section in between was never closed
+            while let Some(open) = self.stack.pop() {
+                if open.name == name {
+                    self.finish(open, Some(tag));
+                    return;
+                }
+                self.unclosed(open);
+            }
+            return;
+        }
+        if let Some(open) = self.stack.pop() {
+            self.errors.push(ParseError {
+                kind: ParseErrorKind::MismatchedEndTag {
+                    expected: open.name.clone(),
+                    found: name,
+                },
+                span: tag.clone(),
+                related: Some(open.start_tag.clone()),
+            });
+            self.finish(open, Some(tag));
+        }
+    }
+
+    fn parameter_declaration(&mut self, start: usize, end: usize) -> Option<ParameterDeclaration> {
+        let words = split_top_level(self.content, start + 2, end - 1, |c| c.is_whitespace());
+        let mut words = words.into_iter();
+        let (type_start, type_end) = words.next()?;
+        let (name_start, name_end) = words.next()?;
+        // `{@java.lang.String name='default'}`
+        let name_end = self.content[name_start..name_end]
+            .find('=')
+            .map(|i| name_start + i)
+            .unwrap_or(name_end);
+        Some(ParameterDeclaration {
+            span: self.index.span(start, end),
+            java_type: Word {
+                value: self.content[type_start..type_end].to_string(),
+                span: self.index.span(type_start, type_end),
+            },
+            name: Word {
+                value: self.content[name_start..name_end].to_string(),
+                span: self.index.span(name_start, name_end),
+            },
+        })
+    }
+}
+
+/// Returns the offset of the `}` that closes the tag. Curly braces inside of string literals are
+/// ignored.
+fn find_tag_end(content: &str, start: usize) -> Option<usize> {
+    let mut quote: Option<u8> = None;
+    for (i, c) in content.as_bytes()[start..].iter().enumerate() {
+        match (quote, c) {
+            (Some(q), c) if q == *c => quote = None,
+            (Some(_), _) => (),
+            (None, b'"') | (None, b'\'') => quote = Some(*c),
+            (None, b'}') => return Some(start + i),
+            (None, b'{') => return None,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Splits the content between start and end on separators that are not inside of quotes or
+/// brackets. Returns the offsets of the non empty pieces.
+fn split_top_level(
+    content: &str,
+    start: usize,
+    end: usize,
+    is_separator: impl Fn(char) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+    let mut piece_start = start;
+    for (i, c) in content[start..end].char_indices() {
+        let i = start + i;
+        match (quote, c) {
+            (Some(q), c) if q == c => quote = None,
+            (Some(_), _) => (),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') => depth += 1,
+            (None, ')') | (None, ']') => depth -= 1,
+            (None, c) if depth == 0 && is_separator(c) => {
+                if piece_start < i {
+                    out.push((piece_start, i));
+                }
+                piece_start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    if piece_start < end {
+        out.push((piece_start, end));
+    }
+    out
+}
+
+fn parse_section_parameters(index: &LineIndex, start: usize, end: usize) -> Vec<SectionParameter> {
+    let content = index.content;
+    split_top_level(content, start, end, |c| c.is_whitespace())
+        .into_iter()
+        .map(|(start, end)| {
+            let text = &content[start..end];
+            let key = text
+                .find('=')
+                .filter(|i| {
+                    *i > 0
+                        && !text[i + 1..].starts_with('=')
+                        && text[..*i]
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                })
+                .map(|i| (text[..i].to_string(), start + i + 1));
+            let (name, value_start) = match key {
+                Some((name, value_start)) => (Some(name), value_start),
+                None => (None, start),
+            };
+            SectionParameter {
+                name,
+                value: content[value_start..end].to_string(),
+                span: index.span(start, end),
+                value_span: index.span(value_start, end),
+            }
+        })
+        .collect()
+}
+
+/// Parses the value of a section parameter as an expression. `items` of `{#for item in items}`.
+pub fn parse_parameter_expression(index: &LineIndex, parameter: &SectionParameter) -> Expression {
+    let span = &parameter.value_span;
+    parse_expression(index, span.start, span.end)
+}
+
+/// Operators of the conditions of `{#if}`. They are not expressions.
+const OPERATORS: [&str; 18] = [
+    "!", "&&", "||", "and", "or", ">", "gt", ">=", "ge", "<", "lt", "<=", "le", "==", "eq", "is",
+    "!=", "ne",
+];
+
+/// The expressions in the parameters of a section. The conditions of `{#if}` and `{#else if}`,
+/// the iterable of a loop, the object of `{#with}`, the value of `{#when}` and the values of
+/// `{#let}`. Aliases, operators, ids and template names are not expressions.
+pub fn section_expressions(index: &LineIndex, section: &Section) -> Vec<Expression> {
+    let parameters = section.parameters();
+    let mut out = vec![];
+    match section.name.as_str() {
+        "if" => {
+            for block in &section.blocks {
+                // `if` of `{#else if a}`
+                let skip = usize::from(block.label == "else");
+                for parameter in block.parameters.iter().skip(skip) {
+                    let span = &parameter.span;
+                    condition_expressions(index, span.start, span.end, &mut out);
+                }
+            }
+        }
+        "for" => out.extend(
+            parameters
+                .get(2)
+                .map(|p| parse_parameter_expression(index, p)),
+        ),
+        "each" | "with" | "when" | "switch" => out.extend(
+            parameters
+                .first()
+                .map(|p| parse_parameter_expression(index, p)),
+        ),
+        "let" | "set" => out.extend(
+            parameters
+                .iter()
+                .filter(|p| p.name.is_some())
+                .map(|p| parse_parameter_expression(index, p)),
+        ),
+        _ => (),
+    }
+    out
+}
+
+/// `item.active` and `count` of `!item.active || (count > 1)`
+fn condition_expressions(index: &LineIndex, start: usize, end: usize, out: &mut Vec<Expression>) {
+    let content = index.content;
+    for (start, end) in split_top_level(content, start, end, |c| c.is_whitespace()) {
+        let text = &content[start..end];
+        if OPERATORS.contains(&text) {
+            continue;
+        }
+        let start = start + text.len() - text.trim_start_matches('!').len();
+        let text = &content[start..end];
+        if text.len() >= 2 && text.starts_with('(') && text.ends_with(')') {
+            condition_expressions(index, start + 1, end - 1, out);
+        } else if start < end {
+            out.push(parse_expression(index, start, end));
+        }
+    }
+}
+
+/// Parses the content between the byte offsets as an expression without curly braces
+pub fn parse_expression(index: &LineIndex, start: usize, end: usize) -> Expression {
+    let content = index.content;
+    let tokens = split_top_level(content, start, end, |c| c.is_whitespace());
+    let mut tokens = tokens.into_iter();
+    let mut out = Expression {
+        span: index.span(start, end),
+        namespace: None,
+        parts: vec![],
+    };
+    let Some((chain_start, chain_end)) = tokens.next() else {
+        return out;
+    };
+    let mut chain_start = chain_start;
+    let chain = &content[chain_start..chain_end];
+    if let Some(i) = chain.find(':') {
+        let namespace = &chain[..i];
+        if !namespace.is_empty()
+            && namespace.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && !chain[i + 1..].starts_with(':')
+        {
+            out.namespace = Some(Word {
+                value: namespace.to_string(),
+                span: index.span(chain_start, chain_start + i),
+            });
+            chain_start += i + 1;
+        }
+    }
+    if is_literal(&content[chain_start..chain_end]) {
+        out.parts.push(ExpressionPart {
+            name: content[chain_start..chain_end].to_string(),
+            span: index.span(chain_start, chain_end),
+            kind: ExpressionPartKind::Literal,
+        });
+    } else {
+        for (start, end) in split_top_level(content, chain_start, chain_end, |c| c == '.') {
+            out.parts.push(parse_expression_part(index, start, end));
+        }
+    }
+    // infix notation: `item.name or 'default'`
+    while let Some((op_start, op_end)) = tokens.next() {
+        let parameters = tokens
+            .next()
+            .map(|(start, end)| vec![parse_expression(index, start, end)])
+            .unwrap_or_default();
+        out.parts.push(ExpressionPart {
+            name: content[op_start..op_end].to_string(),
+            span: index.span(op_start, op_end),
+            kind: ExpressionPartKind::VirtualMethod(parameters),
+        });
+    }
+    out
+}
+
+fn parse_expression_part(index: &LineIndex, start: usize, end: usize) -> ExpressionPart {
+    let content = index.content;
+    let text = &content[start..end];
+    if is_literal(text) {
+        return ExpressionPart {
+            name: text.to_string(),
+            span: index.span(start, end),
+            kind: ExpressionPartKind::Literal,
+        };
+    }
+    if let (Some(open), true) = (text.find('('), text.ends_with(')')) {
+        let parameters = split_top_level(content, start + open + 1, end - 1, |c| c == ',')
+            .into_iter()
+            .map(|(start, end)| {
+                let trimmed = content[start..end].trim_start();
+                let start = end - trimmed.len();
+                let end = start + trimmed.trim_end().len();
+                parse_expression(index, start, end)
+            })
+            .collect();
+        return ExpressionPart {
+            name: text[..open].to_string(),
+            span: index.span(start, start + open),
+            kind: ExpressionPartKind::VirtualMethod(parameters),
+        };
+    }
+    ExpressionPart {
+        name: text.to_string(),
+        span: index.span(start, end),
+        kind: ExpressionPartKind::Property,
+    }
+}
+
+fn is_literal(text: &str) -> bool {
+    text.starts_with('\'')
+        || text.starts_with('"')
+        || matches!(text, "true" | "false" | "null")
+        || text.starts_with(|c: char| c.is_ascii_digit())
+        || (text.starts_with('-') && text[1..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::qute::{parse, ExpressionPartKind, ParseErrorKind, QuteNode};
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    use super::LineIndex;
+
+    #[test]
+    fn parse_nested_sections() {
+        let content = "<ol>
+{#for item in items}
+    {#if item.active}<li>{item.name}</li>{#else}<li>-</li>{/if}
+{/for}
+</ol>";
+        let document = parse(content);
+        assert_eq!(document.errors, vec![]);
+        let sections = document.sections();
+        assert_eq!(
+            sections.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["for", "if"]
+        );
+        let for_section = sections[0];
+        assert_eq!(
+            for_section
+                .parameters()
+                .iter()
+                .map(|p| p.value.as_str())
+                .collect::<Vec<_>>(),
+            vec!["item", "in", "items"]
+        );
+        assert_eq!(
+            for_section.start_tag.range,
+            Range::new(Position::new(1, 0), Position::new(1, 20))
+        );
+        assert_eq!(
+            for_section.end_tag.as_ref().map(|e| e.range),
+            Some(Range::new(Position::new(3, 0), Position::new(3, 6)))
+        );
+        let if_section = sections[1];
+        assert_eq!(
+            if_section
+                .blocks
+                .iter()
+                .map(|b| b.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["if", "else"]
+        );
+    }
+
+    #[test]
+    fn parse_self_closing_and_params() {
+        let content = "{#include foo limit=10 /}{#include bar /}";
+        let document = parse(content);
+        assert_eq!(document.errors, vec![]);
+        let sections = document.sections();
+        assert_eq!(sections.len(), 2);
+        assert!(sections[0].self_closing);
+        let limit = sections[0].parameter("limit").unwrap();
+        assert_eq!(limit.value, "10");
+        assert_eq!(&content[limit.value_span.start..limit.value_span.end], "10");
+        assert_eq!(sections[1].parameters()[0].value, "bar");
+    }
+
+    #[test]
+    fn parse_comments_unparsed_and_text() {
+        let content = "a {! {#if} !} b {| {item} |} c { not an expression }";
+        let document = parse(content);
+        assert_eq!(document.errors, vec![]);
+        let kinds: Vec<&str> = document
+            .nodes
+            .iter()
+            .map(|n| match n {
+                QuteNode::Text(_) => "text",
+                QuteNode::Comment(_) => "comment",
+                QuteNode::Unparsed(_) => "unparsed",
+                QuteNode::Expression(_) => "expression",
+                QuteNode::ParameterDeclaration(_) => "declaration",
+                QuteNode::Section(_) => "section",
+            })
+            .collect();
+        assert_eq!(kinds, vec!["text", "comment", "text", "unparsed", "text"]);
+    }
+
+    #[test]
+    fn parse_expression_parts() {
+        let content = "{inject:bean.name.or('x')} {item.price gt 10}";
+        let document = parse(content);
+        let expressions: Vec<_> = document
+            .walk()
+            .into_iter()
+            .filter_map(|n| match n {
+                QuteNode::Expression(e) => Some(e),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(expressions.len(), 2);
+        let first = expressions[0];
+        assert_eq!(
+            first.namespace.as_ref().map(|n| n.value.as_str()),
+            Some("inject")
+        );
+        assert_eq!(
+            first
+                .parts
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["bean", "name", "or"]
+        );
+        let ExpressionPartKind::VirtualMethod(parameters) = &first.parts[2].kind else {
+            panic!("or must be a virtual method");
+        };
+        assert_eq!(parameters[0].parts[0].kind, ExpressionPartKind::Literal);
+        let span = &parameters[0].span;
+        assert_eq!(&content[span.start..span.end], "'x'");
+        let second = expressions[1];
+        assert_eq!(
+            second
+                .parts
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["item", "price", "gt"]
+        );
+    }
+
+    #[test]
+    fn parse_parameter_declaration() {
+        let document = parse("{@org.acme.Item item}");
+        let Some(QuteNode::ParameterDeclaration(declaration)) = document.nodes.first() else {
+            panic!("expected a parameter declaration");
+        };
+        assert_eq!(declaration.java_type.value, "org.acme.Item");
+        assert_eq!(declaration.name.value, "item");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let document = parse("{#for i in items}{#if i}{/for}");
+        assert_eq!(
+            document
+                .errors
+                .iter()
+                .map(|e| e.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![ParseErrorKind::UnclosedSection("if".to_string())]
+        );
+
+        let document = parse("{#each items}{/for}{/if}");
+        assert_eq!(
+            document
+                .errors
+                .iter()
+                .map(|e| e.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                ParseErrorKind::MismatchedEndTag {
+                    expected: "each".to_string(),
+                    found: "for".to_string()
+                },
+                ParseErrorKind::StrayEndTag("if".to_string())
+            ]
+        );
+
+        let document = parse("{#if a}\n{#else}");
+        assert_eq!(
+            document
+                .errors
+                .iter()
+                .map(|e| e.kind.clone())
+                .collect::<Vec<_>>(),
+            vec![ParseErrorKind::UnclosedSection("if".to_string())]
+        );
+
+        let document = parse("<p>{#fo x\n{#if a}{/if}");
+        assert_eq!(
+            document
+                .errors
+                .iter()
+                .map(|e| (e.kind.clone(), e.span.start, e.span.end))
+                .collect::<Vec<_>>(),
+            vec![(ParseErrorKind::UnterminatedTag, 3, 9)]
+        );
+    }
+
+    #[test]
+    fn javascript_comment_is_text() {
+        let document = parse("<script>{/* x */}</script>{#if a}{/* y */}{/if}");
+        assert!(document.errors.is_empty());
+        assert_eq!(document.sections().len(), 1);
+    }
+
+    #[test]
+    fn tag_at_typed_and_closed_tags() {
+        let content = "a {#fo {#if b}{c}{/if}";
+        let document = parse(content);
+        assert_eq!(document.tag_at(content, 6), Some((2, false)));
+        assert_eq!(document.tag_at(content, 10), Some((7, true)));
+        assert_eq!(document.tag_at(content, 16), Some((14, true)));
+        assert_eq!(document.tag_at(content, 19), Some((17, true)));
+        assert_eq!(document.tag_at(content, 1), None);
+    }
+
+    #[test]
+    fn node_at_innermost() {
+        let content = "{#for item in items}\n<li>{item.name}</li>\n{/for}";
+        let document = parse(content);
+        let offset = content.find("name").unwrap();
+        assert!(matches!(
+            document.node_at(offset),
+            Some(QuteNode::Expression(_))
+        ));
+        assert!(matches!(document.node_at(2), Some(QuteNode::Section(_))));
+    }
+
+    #[test]
+    fn line_index_utf16() {
+        let content = "ä😀\n{x}";
+        let index = LineIndex::new(content);
+        assert_eq!(index.position(6), Position::new(0, 3));
+        assert_eq!(index.offset(Position::new(0, 3)), 6);
+        assert_eq!(index.offset(Position::new(1, 1)), 8);
+    }
+}
@@ -0,0 +1,134 @@
+use dashmap::DashMap;
+use tower_lsp::lsp_types::{Location, Url};
+
+use super::template_reference::TemplateReference;
+
+/// Reverse index of every include and java site that references a template. The references
+/// are grouped by the uri of the file that contains them, so that a changed file can be
+/// replaced.
+#[derive(Debug, Default)]
+pub struct ReferenceIndex {
+    files: DashMap<Url, Vec<TemplateReference>>,
+}
+
+impl ReferenceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces every reference of the file
+    pub fn replace_file(&self, uri: Url, references: Vec<TemplateReference>) {
+        self.files.insert(uri, references);
+    }
+
+    pub fn remove_file(&self, uri: &Url) {
+        self.files.remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// Every reference to the template or the fragment of it with the file that contains it
+    pub fn find(&self, template: &str, fragment: Option<&str>) -> Vec<(Url, TemplateReference)> {
+        let mut out: Vec<(Url, TemplateReference)> = self
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.value()
+                    .iter()
+                    .filter(|r| r.matches(template, fragment))
+                    .map(|r| (file.key().clone(), r.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        out.sort_by(|a, b| {
+            a.0.as_str()
+                .cmp(b.0.as_str())
+                .then(a.1.range.start.cmp(&b.1.range.start))
+        });
+        out
+    }
+
+    pub fn locations(&self, template: &str, fragment: Option<&str>) -> Vec<Location> {
+        self.find(template, fragment)
+            .into_iter()
+            .map(|(uri, reference)| Location::new(uri, reference.range))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Url;
+
+    use crate::parser::{java_template::scan_java_templates, template_reference::scan_includes};
+
+    use super::ReferenceIndex;
+
+    #[test]
+    fn find_template_and_fragment() {
+        let index = ReferenceIndex::new();
+        let page = Url::parse("file:///templates/page.html").unwrap();
+        let resource = Url::parse("file:///src/ItemResource.java").unwrap();
+        index.replace_file(
+            page.clone(),
+            scan_includes(
+                "{#include items /}\n{#include items$row /}\n{#include base /}",
+                "page",
+            ),
+        );
+        index.replace_file(
+            resource.clone(),
+            scan_java_templates(
+                "class A { Template items; void a() { items.getFragment(\"row\"); } }",
+            ),
+        );
+        let lines = |template: &str, fragment: Option<&str>| {
+            index
+                .locations(template, fragment)
+                .into_iter()
+                .map(|l| (l.uri.path().to_string(), l.range.start.line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lines("items", None),
+            vec![
+                ("/src/ItemResource.java".to_string(), 0),
+                ("/src/ItemResource.java".to_string(), 0),
+                ("/templates/page.html".to_string(), 0),
+                ("/templates/page.html".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            lines("items", Some("row")),
+            vec![
+                ("/src/ItemResource.java".to_string(), 0),
+                ("/templates/page.html".to_string(), 1)
+            ]
+        );
+        index.remove_file(&page);
+        assert_eq!(lines("base", None), vec![]);
+    }
+
+    #[test]
+    fn find_local_includes_of_one_template() {
+        let index = ReferenceIndex::new();
+        let items = Url::parse("file:///templates/items.html").unwrap();
+        let orders = Url::parse("file:///templates/orders.html").unwrap();
+        let content = "{#fragment id=row}{/fragment}\n{#include $row /}";
+        index.replace_file(items.clone(), scan_includes(content, "items"));
+        index.replace_file(orders.clone(), scan_includes(content, "orders"));
+        let uris = |template: &str| {
+            index
+                .find(template, Some("row"))
+                .into_iter()
+                .map(|(uri, _)| uri)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uris("items"), vec![items]);
+        assert_eq!(uris("orders"), vec![orders]);
+        assert_eq!(uris(""), vec![]);
+    }
+}
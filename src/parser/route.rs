@@ -18,6 +18,10 @@ pub struct Route {
     /// Specifies an array of classpaths that are expected. In generic route elements
     pub parameters: Vec<Parameter>,
     pub produces_type: MediaType,
+    /// The name of the java class that implements the route
+    pub class_name: String,
+    /// The name of the java method that implements the route
+    pub method_name: String,
 }
 
 impl ToString for Route {
@@ -81,6 +85,8 @@ impl Default for Route {
             path: String::new(),
             parameters: vec![],
             produces_type: MediaType::TextPlain,
+            class_name: String::new(),
+            method_name: String::new(),
         }
     }
 }
@@ -132,7 +138,27 @@ pub enum MediaType {
     TextXml,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+impl MediaType {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            MediaType::ApplicationAtomXml => "application/atom+xml",
+            MediaType::ApplicationFormUrlencoded => "application/x-www-form-urlencoded",
+            MediaType::ApplicationJson => "application/json",
+            MediaType::ApplicationJsonPatchJson => "application/json-patch+json",
+            MediaType::ApplicationOctetStream => "application/octet-stream",
+            MediaType::ApplicationSvgXml => "application/svg+xml",
+            MediaType::ApplicationXhtmlXml => "application/xhtml+xml",
+            MediaType::ApplicationXml => "application/xml",
+            MediaType::MultipartFormData => "multipart/form-data",
+            MediaType::ServerSentEvents => "text/event-stream",
+            MediaType::TextHtml => "text/html",
+            MediaType::TextPlain => "text/plain",
+            MediaType::TextXml => "text/xml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
 pub enum HttpMethod {
     Get,
     Head,
@@ -174,10 +200,8 @@ impl ToString for ParameterType {
     }
 }
 
-pub fn scan_routes() -> Vec<Route> {
-    let template_folder = "./src/main/java/";
-    let path = Path::new(&template_folder);
-    if let Ok(files) = find_files(path) {
+pub fn scan_routes(java_folder: &Path) -> Vec<Route> {
+    if let Ok(files) = find_files(java_folder) {
         return files
             .into_iter()
             .flat_map(|p| {
@@ -225,9 +249,13 @@ fn analyse_class<'a>(
     let Some(base_route) = analyse_modifiers(content, cursor) else {
         return vec![];
     };
+    let mut base_route = base_route;
     cursor.parent();
     cursor.sibling();
     cursor.sibling();
+    if let Ok(class_name) = cursor.node().utf8_text(content.as_bytes()) {
+        base_route.class_name = class_name.to_string();
+    }
     cursor.sibling();
     if cursor.node().kind() == "superclass" {
         cursor.sibling();
@@ -282,6 +310,9 @@ fn analyse_method<'a>(
     cursor.sibling();
     cursor.sibling();
     let method_position = cursor.node().start_position();
+    if let Ok(method_name) = cursor.node().utf8_text(content.as_bytes()) {
+        route.method_name = method_name.to_string();
+    }
     route.implementation = match Url::from_file_path(file_path) {
         Ok(url) => Some(Location::new(
             url,
@@ -503,7 +534,7 @@ fn parse_jakarta_media_type(annotation_name: &str) -> Option<MediaType> {
     }
 }
 
-fn parse_jakarta_http_method_annotation_name(annotation_name: &str) -> Option<HttpMethod> {
+pub fn parse_jakarta_http_method_annotation_name(annotation_name: &str) -> Option<HttpMethod> {
     match annotation_name {
         "GET" => Some(HttpMethod::Get),
         "HEAD" => Some(HttpMethod::Head),
@@ -578,7 +609,9 @@ mod tests {
                     method: HttpMethod::Get,
                     path: "/hello".to_string(),
                     parameters: vec![],
-                    produces_type: MediaType::TextHtml
+                    produces_type: MediaType::TextHtml,
+                    class_name: "BasicResource".to_string(),
+                    method_name: "hello".to_string(),
                 },
                 Route {
                     implementation: None,
@@ -588,7 +621,9 @@ mod tests {
                         name: "name".to_owned(),
                         java_type: ParameterType::String
                     }],
-                    produces_type: MediaType::TextHtml
+                    produces_type: MediaType::TextHtml,
+                    class_name: "BasicResource".to_string(),
+                    method_name: "customer".to_string(),
                 },
                 Route {
                     implementation: None,
@@ -598,7 +633,9 @@ mod tests {
                         name: "name".to_owned(),
                         java_type: ParameterType::String
                     }],
-                    produces_type: MediaType::TextHtml
+                    produces_type: MediaType::TextHtml,
+                    class_name: "BasicResource".to_string(),
+                    method_name: "no_starting_slash".to_string(),
                 },
                 Route {
                     implementation: None,
@@ -615,6 +652,8 @@ mod tests {
                         },
                     ],
                     produces_type: MediaType::ApplicationJson,
+                    class_name: "BasicResource".to_string(),
+                    method_name: "customer_other".to_string(),
                 },
                 Route {
                     implementation: None,
@@ -631,6 +670,8 @@ mod tests {
                         },
                     ],
                     produces_type: MediaType::ApplicationJson,
+                    class_name: "BasicResource".to_string(),
+                    method_name: "no_annotation_path_param".to_string(),
                 },
             ]
         )
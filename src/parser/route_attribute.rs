@@ -0,0 +1,190 @@
+use tree_sitter::{Node, Parser, Query, QueryCursor};
+
+use super::{
+    qute::LineIndex,
+    qute::Span,
+    route::{parse_jakarta_http_method_annotation_name, HttpMethod},
+    route_completion::can_complete_path_for_param_name,
+};
+
+/// A html attribute whose value is a path to a route. `hx-get="/hello"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteAttribute {
+    pub name: String,
+    pub value: String,
+    pub name_span: Span,
+    pub value_span: Span,
+    /// The http method that is used to call the path
+    pub method: Option<HttpMethod>,
+}
+
+impl RouteAttribute {
+    /// The path without query and hash. None when the value can not be checked against the
+    /// routes of the project. Like external urls or values that are computed by an expression.
+    pub fn path(&self) -> Option<&str> {
+        route_path(&self.value)
+    }
+}
+
+/// The path of an attribute value without query and hash. `/hello` for `/hello?page=1#list`.
+pub fn route_path(value: &str) -> Option<&str> {
+    let path = value.trim();
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    if path.is_empty() || !path.starts_with('/') || path.starts_with("//") {
+        return None;
+    }
+    Some(path)
+}
+
+/// Returns every attribute in a html document that can reference a route. `extra_attributes`
+/// are the route attributes of the config.
+pub fn scan_route_attributes(content: &str, extra_attributes: &[String]) -> Vec<RouteAttribute> {
+    let mut parser = Parser::new();
+    let language = tree_sitter_html::language();
+    parser
+        .set_language(language)
+        .expect("Error loading html grammar");
+    let Some(tree) = parser.parse(content, None) else {
+        return vec![];
+    };
+    let query = "(attribute
+  (attribute_name) @name
+  [
+    (attribute_value) @value
+    (quoted_attribute_value (attribute_value) @value)
+  ])";
+    let Ok(query) = Query::new(language, query) else {
+        return vec![];
+    };
+    let index = LineIndex::new(content);
+    let mut cursor = QueryCursor::new();
+    cursor
+        .matches(&query, tree.root_node(), content.as_bytes())
+        .filter_map(|m| {
+            let name = m.captures.first()?.node;
+            let value = m.captures.get(1)?.node;
+            let name_text = name.utf8_text(content.as_bytes()).ok()?;
+            if !can_complete_path_for_param_name(Some(name_text.to_string()), extra_attributes) {
+                return None;
+            }
+            let method = match name_text {
+                "action" => Some(form_method(name, content)),
+                name => http_method_for_attribute(name),
+            };
+            Some(RouteAttribute {
+                name: name_text.to_string(),
+                value: value.utf8_text(content.as_bytes()).ok()?.to_string(),
+                name_span: node_span(&index, name),
+                value_span: node_span(&index, value),
+                method,
+            })
+        })
+        .collect()
+}
+
+/// `hx-post` => POST
+pub fn http_method_for_attribute(name: &str) -> Option<HttpMethod> {
+    match name {
+        "hx-get" => Some(HttpMethod::Get),
+        "hx-post" => Some(HttpMethod::Post),
+        "hx-put" => Some(HttpMethod::Put),
+        "hx-patch" => Some(HttpMethod::Patch),
+        "hx-delete" => Some(HttpMethod::Delete),
+        _ => None,
+    }
+}
+
+/// POST => `hx-post`
+pub fn attribute_for_http_method(method: &HttpMethod) -> Option<&'static str> {
+    match method {
+        HttpMethod::Get => Some("hx-get"),
+        HttpMethod::Post => Some("hx-post"),
+        HttpMethod::Put => Some("hx-put"),
+        HttpMethod::Patch => Some("hx-patch"),
+        HttpMethod::Delete => Some("hx-delete"),
+        _ => None,
+    }
+}
+
+/// Forms are submitted with the method in the method attribute. The default is GET.
+fn form_method(action_name: Node, content: &str) -> HttpMethod {
+    let Some(start_tag) = action_name.parent().and_then(|a| a.parent()) else {
+        return HttpMethod::Get;
+    };
+    let mut cursor = start_tag.walk();
+    let method = start_tag
+        .children(&mut cursor)
+        .filter(|c| c.kind() == "attribute")
+        .find_map(|attribute| {
+            let name = attribute.child(0)?.utf8_text(content.as_bytes()).ok()?;
+            if !name.eq_ignore_ascii_case("method") {
+                return None;
+            }
+            let value = attribute.child(2)?;
+            let value = match value.child(1) {
+                Some(quoted) if quoted.kind() == "attribute_value" => quoted,
+                _ => value,
+            };
+            let value = value.utf8_text(content.as_bytes()).ok()?;
+            parse_jakarta_http_method_annotation_name(&value.to_uppercase())
+        });
+    method.unwrap_or(HttpMethod::Get)
+}
+
+fn node_span(index: &LineIndex, node: Node) -> Span {
+    index.span(node.start_byte(), node.end_byte())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    use crate::parser::route::HttpMethod;
+
+    use super::scan_route_attributes;
+
+    #[test]
+    fn scan_route_attributes_basic() {
+        let content = "<div>
+    <button hx-get=\"/hello/{item.id}?page=1\" hx-target=\"#list\">Load</button>
+    <form action='/hello/customer' method=\"post\"></form>
+    <a href=\"https://quarkus.io\">Quarkus</a>
+</div>";
+        let out = scan_route_attributes(content, &[]);
+        assert_eq!(
+            out.iter()
+                .map(|a| (a.name.as_str(), a.value.as_str(), a.path()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "hx-get",
+                    "/hello/{item.id}?page=1",
+                    Some("/hello/{item.id}")
+                ),
+                ("action", "/hello/customer", Some("/hello/customer")),
+            ]
+        );
+        assert_eq!(
+            out.iter().map(|a| a.method.clone()).collect::<Vec<_>>(),
+            vec![Some(HttpMethod::Get), Some(HttpMethod::Post)]
+        );
+        assert_eq!(
+            out[0].value_span.range,
+            Range::new(Position::new(1, 20), Position::new(1, 43))
+        );
+    }
+
+    #[test]
+    fn scan_route_attributes_extra() {
+        let content = "<div data-url=\"/hello\" hx-get=\"/hello\"></div>";
+        let extra = vec!["data-url".to_string()];
+        let out = scan_route_attributes(content, &extra);
+        assert_eq!(
+            out.iter()
+                .map(|a| (a.name.as_str(), a.method.clone()))
+                .collect::<Vec<_>>(),
+            vec![("data-url", None), ("hx-get", Some(HttpMethod::Get))]
+        );
+    }
+}
@@ -1,15 +1,18 @@
-use dashmap::DashMap;
+use std::path::Path;
+
 use tower_lsp::lsp_types::CompletionItem;
 use tree_sitter::Node;
 
 use crate::parser::html_utils::html_inline;
 
-use super::route::Route;
+use super::{route::Route, route_attribute::http_method_for_attribute, route_index::RouteIndex};
 
 pub fn completion(
-    route_map: &DashMap<String, Route>,
+    route_index: &RouteIndex,
+    module: Option<&Path>,
     line: &str,
     char_pos: usize,
+    extra_attributes: &[String],
 ) -> Vec<CompletionItem> {
     let Some((tree, line)) = html_inline(line) else {
         return vec![];
@@ -24,7 +27,9 @@ pub fn completion(
     if &cursor.node().kind() != &"attribute_value" {
         return vec![];
     }
-    if let Some(value) = get_completion_items(cursor.node(), line, route_map) {
+    if let Some(value) =
+        get_completion_items(cursor.node(), line, route_index, module, extra_attributes)
+    {
         return value;
     }
     vec![]
@@ -33,35 +38,51 @@ pub fn completion(
 fn get_completion_items(
     string_node: Node<'_>,
     content: String,
-    route_map: &DashMap<String, Route>,
+    route_index: &RouteIndex,
+    module: Option<&Path>,
+    extra_attributes: &[String],
 ) -> Option<Vec<CompletionItem>> {
     let param_name = get_param_name(string_node, &content);
     let already_written = match string_node.utf8_text(content.as_bytes()) {
         Ok(s) => s,
         Err(_) => "",
     };
-    if !can_complete_path_for_param_name(param_name) {
+    let method = param_name
+        .as_ref()
+        .and_then(|name| http_method_for_attribute(name));
+    if !can_complete_path_for_param_name(param_name, extra_attributes) {
         return Some(vec![]);
     }
+    let mut routes: Vec<Route> = route_index
+        .routes(module)
+        .into_iter()
+        .filter(|r| method.is_none() || Some(&r.method) == method.as_ref())
+        .collect();
+    routes.dedup_by(|a, b| a.path == b.path && a.method == b.method);
     return Some(
-        route_map
+        routes
             .iter()
-            .map(|r| {
-                CompletionItem {
-                    label: r.key().to_string(),
-                    detail: Some(r.value().to_string()),
-                    insert_text: Some(r.key().trim_start_matches(already_written).to_string()),
-                   ..Default::default()
-                }
+            .map(|r| CompletionItem {
+                label: r.path.to_string(),
+                detail: Some(r.to_string()),
+                insert_text: Some(r.path.trim_start_matches(already_written).to_string()),
+                ..Default::default()
             })
             .collect::<Vec<_>>(),
     );
 }
 
-fn can_complete_path_for_param_name(param_name: Option<String>) -> bool {
+/// `extra_attributes` are the route attributes of the config
+pub fn can_complete_path_for_param_name(
+    param_name: Option<String>,
+    extra_attributes: &[String],
+) -> bool {
     let Some(param_name) = param_name else {
         return false;
     };
+    if extra_attributes.contains(&param_name) {
+        return true;
+    }
     match param_name.as_str() {
         // html
         "action" => true,
@@ -70,12 +91,13 @@ fn can_complete_path_for_param_name(param_name: Option<String>) -> bool {
         "hx-post" => true,
         "hx-put" => true,
         "hx-path" => true,
+        "hx-patch" => true,
         "hx-delete" => true,
         _ => false,
     }
 }
 
-fn get_param_name(node: Node, content: &str) -> Option<String> {
+pub fn get_param_name(node: Node, content: &str) -> Option<String> {
     let Some(node) = node.prev_sibling() else {
         return None;
     };
@@ -96,22 +118,28 @@ fn get_param_name(node: Node, content: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use dashmap::DashMap;
     use pretty_assertions::assert_eq;
     use tower_lsp::lsp_types::CompletionItem;
 
-    use crate::parser::{route::Route, route_completion::completion};
+    use crate::parser::{
+        route::{HttpMethod, Route},
+        route_completion::completion,
+        route_index::RouteIndex,
+    };
 
     #[test]
     fn completion_basic() {
-        let dm = DashMap::new();
-        dm.insert("/start".to_string(), Route::default());
-        let out = completion(&dm, "<button hx-get=\"/s\" hx-trigger=\"click\" hx-target=\"#selectStyle\" hx-swap=\"outerHTML\"></button>", 18);
+        let dm = RouteIndex::new();
+        dm.insert(Route {
+            path: "/start".to_string(),
+            ..Default::default()
+        });
+        let out = completion(&dm, None, "<button hx-get=\"/s\" hx-trigger=\"click\" hx-target=\"#selectStyle\" hx-swap=\"outerHTML\"></button>", 18, &[]);
         assert_eq!(
             out,
             vec![CompletionItem {
                 label: "/start".to_string(),
-                detail: Some("GET: \n".to_string()),
+                detail: Some("GET: /start\n".to_string()),
                 insert_text: Some("tart".to_string()),
                 ..CompletionItem::default()
             }]
@@ -119,25 +147,47 @@ mod tests {
     }
     #[test]
     fn completion_basic_not() {
-        let dm = DashMap::new();
-        dm.insert("".to_string(), Route::default());
-        let out = completion(&dm, "<button hx-get=\"/sel\" hx-trigger=\"click\" hx-target=\"#selectStyle\" hx-swap=\"outerHTML\"></button>", 63);
+        let dm = RouteIndex::new();
+        dm.insert(Route::default());
+        let out = completion(&dm, None, "<button hx-get=\"/sel\" hx-trigger=\"click\" hx-target=\"#selectStyle\" hx-swap=\"outerHTML\"></button>", 63, &[]);
         assert_eq!(out, vec![])
     }
 
     #[test]
     fn completion_multi_line() {
-        let dm = DashMap::new();
-        dm.insert("/start".to_string(), Route::default());
-        let out = completion(&dm, "hx-get=\"/\"", 9);
+        let dm = RouteIndex::new();
+        dm.insert(Route {
+            path: "/start".to_string(),
+            ..Default::default()
+        });
+        let out = completion(&dm, None, "hx-get=\"/\"", 9, &[]);
         assert_eq!(
             out,
             vec![CompletionItem {
                 label: "/start".to_string(),
-                detail: Some("GET: \n".to_string()),
+                detail: Some("GET: /start\n".to_string()),
                 insert_text: Some("start".to_string()),
                 ..CompletionItem::default()
             }]
         )
     }
+
+    #[test]
+    fn completion_only_matching_method() {
+        let dm = RouteIndex::new();
+        dm.insert(Route {
+            path: "/start".to_string(),
+            ..Default::default()
+        });
+        dm.insert(Route {
+            method: HttpMethod::Post,
+            path: "/save".to_string(),
+            ..Default::default()
+        });
+        let out = completion(&dm, None, "hx-post=\"/\"", 10, &[]);
+        assert_eq!(
+            out.into_iter().map(|c| c.label).collect::<Vec<_>>(),
+            vec!["/save".to_string()]
+        )
+    }
 }
@@ -1,51 +1,45 @@
-use crate::parser::html_utils::html_inline;
+use std::path::Path;
 
-use super::route::Route;
-use dashmap::DashMap;
+use crate::parser::html_utils::attribute_at;
+
+use super::{
+    route_attribute::{http_method_for_attribute, route_path},
+    route_index::RouteIndex,
+};
 use tower_lsp::lsp_types::{GotoDefinitionResponse, Position};
 //use tree_sitter::{Parser, TreeCursor};
 
 pub fn get_definition(
-    route_map: &DashMap<String, Route>,
+    route_index: &RouteIndex,
+    module: Option<&Path>,
     line: &str,
     position: &Position,
 ) -> Option<GotoDefinitionResponse> {
-    let Some((tree, line)) = html_inline(line) else {
-        return None;
-    };
-    let mut cursor = tree.walk();
-    for _i in 0..10 {
-        cursor.goto_first_child_for_point(tree_sitter::Point {
-            row: 0,
-            column: (position.character as usize) + 8,
-        });
-    }
-    if &cursor.node().kind() != &"attribute_value" {
+    let Some(attribute) = attribute_at(line, position.character as usize) else {
         // When this is not a attribute_value then we cannot provide as look
         return None;
-    }
-    let Ok(url) = &cursor.node().utf8_text(line.as_bytes()) else {
-        // When we are not able to get the path we are not able to find out with path
-        return None;
     };
-    get_related_route(route_map, url)
-        .filter(|r| r.implementation.is_some())
-        .map(|route| GotoDefinitionResponse::Scalar(route.implementation.clone().unwrap()))
-}
-
-fn get_related_route<'a>(route_map: &'a DashMap<String, Route>, url: &'a str) -> Option<Route> {
-    let url = without_vars(url);
-    return route_map
+    let method = attribute
+        .name
+        .and_then(|name| http_method_for_attribute(&name));
+    // Prefer the overload that is called by the attribute
+    let mut locations: Vec<_> = route_index
+        .find_preferred(route_path(&attribute.value)?, method.as_ref(), module)
         .into_iter()
-        .find(|e| without_vars(e.key()) == url)
-        .map(|e| e.value().to_owned());
+        .filter_map(|r| r.implementation)
+        .collect();
+    match locations.len() {
+        0 => None,
+        1 => locations.pop().map(GotoDefinitionResponse::Scalar),
+        _ => Some(GotoDefinitionResponse::Array(locations)),
+    }
 }
 
 /// In qute the route path could be partialy filled with variables. We remove these dynamic parts
 /// to match against urls.
 /// In quarkus route urls we can have variables we remove these so we can match them aginst the
 /// qute typed one.
-fn without_vars(url: &str) -> String {
+pub fn without_vars(url: &str) -> String {
     let mut open = false;
     let mut out = String::new();
     for c in url.chars() {
@@ -64,28 +58,31 @@ fn without_vars(url: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use dashmap::DashMap;
-    use tower_lsp::lsp_types::{GotoDefinitionResponse, Position, Url};
+    use std::path::Path;
 
-    use crate::parser::{route::Route, route_definiton::without_vars};
+    use tower_lsp::lsp_types::{GotoDefinitionResponse, Location, Position, Range, Url};
+
+    use crate::parser::{
+        route::{HttpMethod, Route},
+        route_definiton::without_vars,
+        route_index::RouteIndex,
+    };
 
     use super::get_definition;
 
     #[test]
     fn route_definition_basic() {
-        let map = DashMap::new();
-        map.insert(
-            "/{first_param}/select/{second_param}".to_owned(),
-            Route {
-                implementation: Some(tower_lsp::lsp_types::Location {
-                    uri: Url::parse("http://localhost/src/test.java").unwrap(),
-                    range: tower_lsp::lsp_types::Range::default(),
-                }),
-                ..Default::default()
-            },
-        );
+        let map = RouteIndex::new();
+        map.insert(Route {
+            path: "/{first_param}/select/{second_param}".to_owned(),
+            implementation: Some(tower_lsp::lsp_types::Location {
+                uri: Url::parse("http://localhost/src/test.java").unwrap(),
+                range: tower_lsp::lsp_types::Range::default(),
+            }),
+            ..Default::default()
+        });
         let pos = Position::new(0, 12);
-        let out = get_definition(&map, "			hx-get=\"/{id}/select/{toSelect}\"", &pos);
+        let out = get_definition(&map, None, "			hx-get=\"/{id}/select/{toSelect}\"", &pos);
 
         assert_eq!(
             out,
@@ -98,6 +95,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn route_definition_overloads() {
+        let map = RouteIndex::new();
+        let uri = Url::parse("http://localhost/src/test.java").unwrap();
+        let get = Location::new(
+            uri.clone(),
+            Range::new(Position::new(1, 0), Position::new(1, 0)),
+        );
+        let put = Location::new(uri, Range::new(Position::new(5, 0), Position::new(5, 0)));
+        map.insert(Route {
+            path: "/customer/{name}".to_owned(),
+            method: HttpMethod::Get,
+            implementation: Some(get.clone()),
+            ..Default::default()
+        });
+        map.insert(Route {
+            path: "/customer/{name}".to_owned(),
+            method: HttpMethod::Put,
+            implementation: Some(put.clone()),
+            ..Default::default()
+        });
+
+        let out = get_definition(
+            &map,
+            None,
+            "hx-put=\"/customer/{c.name}\"",
+            &Position::new(0, 12),
+        );
+        assert_eq!(out, Some(GotoDefinitionResponse::Scalar(put.clone())));
+
+        let out = get_definition(
+            &map,
+            None,
+            "hx-post=\"/customer/{c.name}\"",
+            &Position::new(0, 12),
+        );
+        assert_eq!(
+            out,
+            Some(GotoDefinitionResponse::Array(vec![get, put.clone()]))
+        );
+
+        let out = get_definition(
+            &map,
+            None,
+            "hx-put=\"/customer/{c.name}?x=1#top\"",
+            &Position::new(0, 12),
+        );
+        assert_eq!(out, Some(GotoDefinitionResponse::Scalar(put)));
+    }
+
+    #[test]
+    fn route_of_other_module() {
+        let map = RouteIndex::new();
+        let path = "/project/api/src/main/java/org/acme/ItemResource.java";
+        let location = Location::new(Url::from_file_path(path).unwrap(), Range::default());
+        map.replace_file(
+            location.uri.as_str(),
+            Path::new("/project/api"),
+            vec![Route {
+                path: "/items/{id}".to_owned(),
+                implementation: Some(location.clone()),
+                ..Default::default()
+            }],
+        );
+        let out = get_definition(
+            &map,
+            Some(Path::new("/project/web")),
+            "hx-get=\"/items/{item.id}\"",
+            &Position::new(0, 12),
+        );
+        assert_eq!(out, Some(GotoDefinitionResponse::Scalar(location)));
+    }
+
     #[test]
     fn without_vars_base() {
         assert_eq!(without_vars("/{id}/select/{participant.uuid}"), "//select/");
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range};
+
+use crate::parser::html_utils::attribute_at;
+
+use super::{
+    route::Route,
+    route_attribute::{http_method_for_attribute, route_path},
+    route_index::RouteIndex,
+};
+
+/// Shows the routes that are called by the attribute value below the cursor
+pub fn hover(
+    route_index: &RouteIndex,
+    module: Option<&Path>,
+    line: &str,
+    position: &Position,
+) -> Option<Hover> {
+    let attribute = attribute_at(line, position.character as usize)?;
+    let method = attribute
+        .name
+        .as_ref()
+        .and_then(|name| http_method_for_attribute(name));
+    let routes = route_index.find_preferred(route_path(&attribute.value)?, method.as_ref(), module);
+    if routes.is_empty() {
+        return None;
+    }
+    let value = routes
+        .iter()
+        .map(route_markdown)
+        .collect::<Vec<_>>()
+        .join("\n---\n");
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(Range::new(
+            Position::new(position.line, attribute.start as u32),
+            Position::new(position.line, attribute.end as u32),
+        )),
+    })
+}
+
+fn route_markdown(route: &Route) -> String {
+    let mut out = format!("**{}** `{}`\n", route.method.to_string(), route.path);
+    if !route.parameters.is_empty() {
+        out.push_str("\nPath parameters:\n");
+        for parameter in &route.parameters {
+            out.push_str(&format!("- `{}`\n", parameter.to_string()));
+        }
+    }
+    out.push_str(&format!("\nProduces: `{}`\n", route.produces_type.mime()));
+    if !route.class_name.is_empty() {
+        out.push_str(&format!(
+            "\nImplemented by `{}#{}`\n",
+            route.class_name, route.method_name
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{HoverContents, MarkupContent, MarkupKind, Position, Range};
+
+    use crate::parser::{route::analyse_file, route_index::RouteIndex};
+
+    use super::hover;
+
+    #[test]
+    fn hover_overload() {
+        static FILE_CONTENT: &str = include_str!("../../test/BasicResource.java");
+        let index = RouteIndex::from_routes(analyse_file("".into(), FILE_CONTENT));
+        let out = hover(
+            &index,
+            None,
+            "<button hx-get=\"/hello/customer/{c.name}\"></button>",
+            &Position::new(3, 20),
+        )
+        .unwrap();
+        assert_eq!(
+            out.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "**GET** `/hello/customer/{name}`
+
+Path parameters:
+- `String name`
+
+Produces: `text/html`
+
+Implemented by `BasicResource#customer`
+"
+                .to_string()
+            })
+        );
+        assert_eq!(
+            out.range,
+            Some(Range::new(Position::new(3, 16), Position::new(3, 40)))
+        );
+    }
+
+    #[test]
+    fn hover_with_query_and_hash() {
+        static FILE_CONTENT: &str = include_str!("../../test/BasicResource.java");
+        let index = RouteIndex::from_routes(analyse_file("".into(), FILE_CONTENT));
+        let out = hover(
+            &index,
+            None,
+            "<a hx-get=\"/hello/customer/{c.name}?x=1#top\"></a>",
+            &Position::new(0, 16),
+        );
+        assert!(out.is_some());
+    }
+
+    #[test]
+    fn hover_no_route() {
+        let index = RouteIndex::new();
+        assert_eq!(
+            hover(&index, None, "hx-get=\"/unknown\"", &Position::new(0, 10)),
+            None
+        );
+    }
+}
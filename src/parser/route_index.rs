@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use dashmap::DashMap;
+
+use super::{
+    route::{HttpMethod, Route},
+    route_definiton::without_vars,
+};
+
+/// All routes of the project. The routes are grouped by the java file that implements them. So
+/// that routes with the same path but a different http method are all kept.
+#[derive(Debug, Default)]
+pub struct RouteIndex {
+    files: DashMap<String, RouteFile>,
+}
+
+/// The routes of one java file
+#[derive(Debug, Default)]
+struct RouteFile {
+    /// The root of the module that contains the file. Empty when it is not known.
+    module: PathBuf,
+    routes: Vec<Route>,
+}
+
+impl RouteFile {
+    /// Whether the file is in the module. Every file is when no module is given.
+    fn in_module(&self, module: Option<&Path>) -> bool {
+        module.is_none_or(|module| self.module == module)
+    }
+}
+
+impl RouteIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_routes(routes: Vec<Route>) -> Self {
+        let index = Self::new();
+        for route in routes {
+            index.insert(route);
+        }
+        index
+    }
+
+    pub fn insert(&self, route: Route) {
+        self.files
+            .entry(get_source(&route))
+            .or_default()
+            .routes
+            .push(route);
+    }
+
+    /// Replaces every route implemented in the file. `source` is the uri of the java file.
+    pub fn replace_file(&self, source: &str, module: &Path, routes: Vec<Route>) {
+        self.files.insert(
+            source.to_string(),
+            RouteFile {
+                module: module.to_path_buf(),
+                routes,
+            },
+        );
+    }
+
+    /// Removes every route implemented in the file
+    pub fn remove_file(&self, source: &str) {
+        self.files.remove(source);
+    }
+
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// All routes of the project. The routes of the module come first, the ones of the other
+    /// modules follow. Both parts are sorted by path and http method.
+    pub fn routes(&self, module: Option<&Path>) -> Vec<Route> {
+        self.collect(module, |_| true)
+    }
+
+    /// Returns every route that matches the url regardless of the http method. The routes of the
+    /// module come first. Variables in the url and in the route path are ignored.
+    pub fn find(&self, url: &str, module: Option<&Path>) -> Vec<Route> {
+        let url = without_vars(url);
+        self.collect(module, |r| without_vars(&r.path) == url)
+    }
+
+    /// Whether any route of the project matches the url like in `find`
+    pub fn contains(&self, url: &str) -> bool {
+        let url = without_vars(url);
+        self.files
+            .iter()
+            .any(|f| f.routes.iter().any(|r| without_vars(&r.path) == url))
+    }
+
+    fn collect(&self, module: Option<&Path>, filter: impl Fn(&Route) -> bool) -> Vec<Route> {
+        let mut own = vec![];
+        let mut other = vec![];
+        for file in self.files.iter() {
+            let out = if file.in_module(module) {
+                &mut own
+            } else {
+                &mut other
+            };
+            out.extend(file.routes.iter().filter(|r| filter(r)).cloned());
+        }
+        sort_routes(&mut own);
+        sort_routes(&mut other);
+        own.extend(other);
+        own
+    }
+
+    /// Like find but only returns the routes with the http method when there are any
+    pub fn find_preferred(
+        &self,
+        url: &str,
+        method: Option<&HttpMethod>,
+        module: Option<&Path>,
+    ) -> Vec<Route> {
+        let routes = self.find(url, module);
+        if routes.iter().any(|r| Some(&r.method) == method) {
+            return routes
+                .into_iter()
+                .filter(|r| Some(&r.method) == method)
+                .collect();
+        }
+        routes
+    }
+}
+
+fn sort_routes(routes: &mut [Route]) {
+    routes.sort_by(|a, b| a.path.cmp(&b.path).then(a.method.cmp(&b.method)));
+}
+
+/// The uri of the implementing java file
+fn get_source(route: &Route) -> String {
+    route
+        .implementation
+        .as_ref()
+        .map(|l| l.uri.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use std::path::{Path, PathBuf};
+
+    use tower_lsp::lsp_types::Url;
+
+    use crate::parser::route::{analyse_file, HttpMethod, Route};
+
+    use super::RouteIndex;
+
+    #[test]
+    fn keeps_routes_with_same_path() {
+        static FILE_CONTENT: &str = include_str!("../../test/BasicResource.java");
+        let index = RouteIndex::from_routes(analyse_file("".into(), FILE_CONTENT));
+        assert_eq!(index.routes(None).len(), 5);
+        let out = index.find("/hello/customer/{c.name}/{c.suffix}", None);
+        assert_eq!(
+            out.iter().map(|r| r.method.clone()).collect::<Vec<_>>(),
+            vec![HttpMethod::Put, HttpMethod::Put]
+        );
+        assert!(index.contains("/hello/customer/{a}/{b}"));
+        assert!(!index.contains("/hello/unknown"));
+    }
+
+    #[test]
+    fn replace_and_remove_file() {
+        static FILE_CONTENT: &str = include_str!("../../test/BasicResource.java");
+        let path = PathBuf::from("/project/BasicResource.java");
+        let source = Url::from_file_path(&path).unwrap().to_string();
+        let index = RouteIndex::from_routes(analyse_file(path.clone(), FILE_CONTENT));
+        let changed =
+            FILE_CONTENT.replace("@Path(\"no_starting_slash/{name}\")", "@Path(\"renamed\")");
+        index.replace_file(&source, Path::new("/project"), analyse_file(path, &changed));
+        assert_eq!(index.routes(None).len(), 5);
+        assert_eq!(index.find("/hello/no_starting_slash/{name}", None).len(), 0);
+        assert_eq!(index.find("/hello/renamed", None).len(), 1);
+        index.remove_file(&source);
+        assert_eq!(index.routes(None).len(), 0);
+    }
+
+    #[test]
+    fn routes_of_module_first() {
+        static FILE_CONTENT: &str = include_str!("../../test/BasicResource.java");
+        let index = RouteIndex::new();
+        for module in ["/project/web", "/project/api"] {
+            let path = PathBuf::from(module).join("BasicResource.java");
+            let source = Url::from_file_path(&path).unwrap().to_string();
+            index.replace_file(&source, Path::new(module), analyse_file(path, FILE_CONTENT));
+        }
+        let module_of = |routes: Vec<Route>| {
+            routes
+                .iter()
+                .map(|r| {
+                    let uri = &r.implementation.as_ref().unwrap().uri;
+                    uri.path().split('/').nth(2).unwrap().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            module_of(index.find(
+                "/hello/no_starting_slash/{name}",
+                Some(Path::new("/project/web"))
+            )),
+            vec!["web", "api"]
+        );
+        assert_eq!(
+            module_of(index.find(
+                "/hello/no_starting_slash/{name}",
+                Some(Path::new("/project/api"))
+            )),
+            vec!["api", "web"]
+        );
+        assert_eq!(index.routes(Some(Path::new("/project/other"))).len(), 10);
+    }
+}
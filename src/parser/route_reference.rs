@@ -0,0 +1,93 @@
+use tower_lsp::lsp_types::Position;
+use tree_sitter::Parser;
+
+use crate::extraction::to_lsp_position;
+
+use super::{
+    qute::LineIndex,
+    route::{HttpMethod, Route},
+    route_attribute::RouteAttribute,
+    route_definiton::without_vars,
+};
+
+/// Links are not in the route attributes because they can not be completed like htmx
+/// attributes, but they still reference routes.
+pub static LINK_ATTRIBUTE: &str = "href";
+
+/// The position of the name of the java method that contains the position. This is the
+/// position that `Route::implementation` points to.
+pub fn method_name_at(content: &str, position: Position) -> Option<Position> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_java::language())
+        .expect("Error loading java grammar");
+    let tree = parser.parse(content, None)?;
+    let offset = LineIndex::new(content).offset(position);
+    let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+    while node.kind() != "method_declaration" {
+        node = node.parent()?;
+    }
+    let name = node.child_by_field_name("name")?;
+    Some(to_lsp_position(name.start_position()))
+}
+
+/// Whether the attribute calls the route. Variables in the paths are ignored. Attributes
+/// without a http method are links and call GET routes.
+pub fn references_route(route: &Route, attribute: &RouteAttribute) -> bool {
+    let Some(path) = attribute.path() else {
+        return false;
+    };
+    let method = attribute.method.clone().unwrap_or(HttpMethod::Get);
+    method == route.method && without_vars(path) == without_vars(&route.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Position;
+
+    use crate::parser::{
+        route::{HttpMethod, Route},
+        route_attribute::scan_route_attributes,
+    };
+
+    use super::{method_name_at, references_route, LINK_ATTRIBUTE};
+
+    #[test]
+    fn method_name_at_annotation() {
+        let content = "@Path(\"/hello\")
+public class BasicResource {
+    @GET
+    @Path(\"/customer/{name}\")
+    public TemplateInstance customer(@PathParam(\"name\") String name) {
+        return null;
+    }
+}";
+        let name = Some(Position::new(4, 28));
+        assert_eq!(method_name_at(content, Position::new(2, 6)), name);
+        assert_eq!(method_name_at(content, Position::new(5, 10)), name);
+        assert_eq!(method_name_at(content, Position::new(1, 10)), None);
+    }
+
+    #[test]
+    fn references_route_by_method() {
+        let route = Route {
+            method: HttpMethod::Get,
+            path: "/hello/customer/{name}".to_string(),
+            ..Default::default()
+        };
+        let content = "<a href=\"/hello/customer/{c.name}\"></a>
+<button hx-get=\"/hello/customer/{c.name}?full=true\"></button>
+<button hx-post=\"/hello/customer/{c.name}\"></button>
+<a href=\"/hello\"></a>";
+        let attributes = scan_route_attributes(content, &[LINK_ATTRIBUTE.to_string()]);
+        assert_eq!(
+            attributes
+                .iter()
+                .filter(|a| references_route(&route, a))
+                .map(|a| a.value_span.range.start.line)
+                .collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+}
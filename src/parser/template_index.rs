@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::Url;
+
+use super::fragemnt::Fragment;
+
+/// The templates of every template folder by the canonical uri of the file, so that the
+/// templates are known without walking the folders again.
+#[derive(Debug, Default)]
+pub struct TemplateIndex {
+    files: DashMap<Url, IndexedTemplate>,
+}
+
+/// One template file with the module that contains it
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedTemplate {
+    /// The root of the module. Templates of different modules may have the same id.
+    pub module: PathBuf,
+    /// `folder/file`
+    pub template: String,
+    /// The fragments with their full id `folder/file$frag`
+    pub fragments: Vec<Fragment>,
+}
+
+impl IndexedTemplate {
+    /// Whether the template is in the module. Every template is when no module is given.
+    fn in_module(&self, module: Option<&Path>) -> bool {
+        module.is_none_or(|module| self.module == module)
+    }
+}
+
+impl TemplateIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn replace_file(&self, uri: Url, template: IndexedTemplate) {
+        self.files.insert(uri, template);
+    }
+
+    pub fn remove_file(&self, uri: &Url) {
+        self.files.remove(uri);
+    }
+
+    pub fn clear(&self) {
+        self.files.clear();
+    }
+
+    /// `folder/file` of every template of the project. The templates of the module come first and
+    /// hide the ones with the same id in other modules, like `project::template_folders` does.
+    pub fn templates(&self, module: Option<&Path>) -> Vec<String> {
+        let mut out: Vec<String> = vec![];
+        for file in self.ordered(module) {
+            if !out.contains(&file.template) {
+                out.push(file.template);
+            }
+        }
+        out
+    }
+
+    /// Every fragment of the project. The fragments of the module come first and hide the ones
+    /// with the same id in other modules. Without a module every fragment is kept.
+    pub fn fragments(&self, module: Option<&Path>) -> Vec<Fragment> {
+        let mut out: Vec<Fragment> = vec![];
+        for fragment in self.ordered(module).into_iter().flat_map(|f| f.fragments) {
+            if module.is_none() || !out.iter().any(|f| f.id == fragment.id) {
+                out.push(fragment);
+            }
+        }
+        out
+    }
+
+    /// The templates of the module followed by the ones of the other modules
+    fn ordered(&self, module: Option<&Path>) -> Vec<IndexedTemplate> {
+        let mut out: Vec<IndexedTemplate> = self.files.iter().map(|f| f.value().clone()).collect();
+        out.sort_by_key(|file| !file.in_module(module));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Range, Url};
+
+    use crate::parser::fragemnt::Fragment;
+
+    use super::{IndexedTemplate, TemplateIndex};
+
+    #[test]
+    fn same_template_in_two_modules() {
+        let index = TemplateIndex::new();
+        for module in ["/project/web", "/project/admin"] {
+            let path = format!("{module}/src/main/resources/templates/items.html");
+            index.replace_file(
+                Url::from_file_path(&path).unwrap(),
+                IndexedTemplate {
+                    module: PathBuf::from(module),
+                    template: "items".to_string(),
+                    fragments: vec![Fragment {
+                        id: "items$row".to_string(),
+                        source: path,
+                        range: Range::default(),
+                    }],
+                },
+            );
+        }
+        let web = Some(Path::new("/project/web"));
+        assert_eq!(index.templates(web), vec!["items".to_string()]);
+        assert_eq!(
+            index
+                .fragments(web)
+                .into_iter()
+                .map(|f| f.source)
+                .collect::<Vec<_>>(),
+            vec!["/project/web/src/main/resources/templates/items.html".to_string()]
+        );
+        assert_eq!(index.fragments(None).len(), 2);
+        assert_eq!(
+            index.templates(Some(Path::new("/project/other"))),
+            vec!["items".to_string()]
+        );
+    }
+}
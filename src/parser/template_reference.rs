@@ -0,0 +1,160 @@
+use tower_lsp::lsp_types::Range;
+
+use super::{
+    include::{include_from_section, QuteInclude},
+    qute::{self, LineIndex},
+};
+
+/// A place in a template or java file that references a template or a fragment of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateReference {
+    /// `folder/file`. The template that contains a local include like `{#include $item /}`.
+    pub template: String,
+    pub fragment: Option<String>,
+    pub kind: TemplateReferenceKind,
+    /// The text that references the template. `page$item` of `{#include page$item /}`
+    pub range: Range,
+    /// The part of the range that is the fragment id. `item` of `{#include page$item /}`
+    pub fragment_range: Option<Range>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateReferenceKind {
+    /// `{#include page$item /}`
+    Include,
+    /// `Template page;`
+    Field,
+    /// `@Location("page.html")`
+    Location,
+    /// `static native TemplateInstance page$item();` in a `@CheckedTemplate` class
+    CheckedTemplate,
+    /// `page.getFragment("item")`
+    GetFragment,
+}
+
+impl TemplateReference {
+    /// The part of the range that is the template. Only includes and locations start with the
+    /// template, the names of fields and checked template methods do not.
+    pub fn template_range(&self) -> Option<Range> {
+        if !matches!(
+            self.kind,
+            TemplateReferenceKind::Include | TemplateReferenceKind::Location
+        ) {
+            return None;
+        }
+        // `$item` of a local include does not contain the template
+        if self
+            .fragment_range
+            .is_some_and(|r| r.start.character == self.range.start.character + 1)
+        {
+            return None;
+        }
+        let mut end = self.range.start;
+        end.character += self.template.encode_utf16().count() as u32;
+        Some(Range::new(self.range.start, end))
+    }
+
+    /// Whether this references the template or the fragment of it
+    pub fn matches(&self, template: &str, fragment: Option<&str>) -> bool {
+        match fragment {
+            Some(fragment) => {
+                self.template == template && self.fragment.as_deref() == Some(fragment)
+            }
+            None => self.template == template,
+        }
+    }
+}
+
+/// Every include in a template. Local includes like `{#include $item /}` reference a fragment
+/// of `current_template`.
+pub fn scan_includes(content: &str, current_template: &str) -> Vec<TemplateReference> {
+    let index = LineIndex::new(content);
+    qute::parse(content)
+        .sections()
+        .into_iter()
+        .filter_map(|section| {
+            let include = include_from_section(section)?;
+            let value = section.parameters().iter().find(|p| p.name.is_none())?;
+            let span = &value.value_span;
+            Some(match include {
+                QuteInclude::Basic(template) => TemplateReference {
+                    template,
+                    fragment: None,
+                    kind: TemplateReferenceKind::Include,
+                    range: span.range,
+                    fragment_range: None,
+                },
+                QuteInclude::Fragment(fragment) => {
+                    let fragment_start = span.start + fragment.template.len() + 1;
+                    let template = match fragment.template.is_empty() {
+                        true => current_template.to_string(),
+                        false => fragment.template,
+                    };
+                    TemplateReference {
+                        template,
+                        fragment: Some(fragment.fragment),
+                        kind: TemplateReferenceKind::Include,
+                        range: span.range,
+                        fragment_range: Some(index.span(fragment_start, span.end).range),
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    use crate::parser::java_template::scan_java_templates;
+
+    use super::{scan_includes, TemplateReference, TemplateReferenceKind};
+
+    #[test]
+    fn scan_includes_basic() {
+        let content = "<div>
+  {#include base}{/include}
+  {#include items$item item=item /}
+</div>";
+        assert_eq!(
+            scan_includes(content, "page"),
+            vec![
+                TemplateReference {
+                    template: "base".to_string(),
+                    fragment: None,
+                    kind: TemplateReferenceKind::Include,
+                    range: Range::new(Position::new(1, 12), Position::new(1, 16)),
+                    fragment_range: None,
+                },
+                TemplateReference {
+                    template: "items".to_string(),
+                    fragment: Some("item".to_string()),
+                    kind: TemplateReferenceKind::Include,
+                    range: Range::new(Position::new(2, 12), Position::new(2, 22)),
+                    fragment_range: Some(Range::new(Position::new(2, 18), Position::new(2, 22))),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn template_range() {
+        let includes = scan_includes("  {#include items$row /}{#include $row /}", "page");
+        assert_eq!(
+            includes[0].template_range(),
+            Some(Range::new(Position::new(0, 12), Position::new(0, 17)))
+        );
+        assert_eq!(includes[1].template, "page");
+        assert_eq!(includes[1].template_range(), None);
+        let java = scan_java_templates(
+            "class A {\n  @Location(\"/detail/items.html\") Template a;\n  Template items;\n}",
+        );
+        assert_eq!(
+            java[0].template_range(),
+            Some(Range::new(Position::new(1, 14), Position::new(1, 26)))
+        );
+        assert_eq!(java[1].template_range(), None);
+    }
+}
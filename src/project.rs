@@ -0,0 +1,233 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Config;
+
+/// A maven or gradle module. Single module projects only have the root module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    /// Path relative to the project root. Empty for the root module.
+    pub name: String,
+    pub root: PathBuf,
+}
+
+impl Module {
+    pub fn template_folders(&self, config: &Config) -> Vec<PathBuf> {
+        config
+            .template_roots
+            .iter()
+            .map(|folder| self.root.join(folder))
+            .collect()
+    }
+
+    pub fn java_folders(&self, config: &Config) -> Vec<PathBuf> {
+        config
+            .source_roots
+            .iter()
+            .map(|folder| self.root.join(folder))
+            .collect()
+    }
+}
+
+/// Finds the root module and every module that is declared in the `pom.xml` or
+/// `settings.gradle(.kts)` files of the project. Nested maven modules are included.
+pub fn discover_modules(root: &Path) -> Vec<Module> {
+    let mut out = vec![Module {
+        name: String::new(),
+        root: root.to_path_buf(),
+    }];
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(folder) = pending.pop() {
+        for name in declared_modules(&folder) {
+            let module_root = folder.join(&name);
+            if !module_root.is_dir() || out.iter().any(|m| m.root == module_root) {
+                continue;
+            }
+            let name = module_root
+                .strip_prefix(root)
+                .unwrap_or(&module_root)
+                .to_string_lossy()
+                .to_string();
+            out.push(Module {
+                name,
+                root: module_root.clone(),
+            });
+            pending.push(module_root);
+        }
+    }
+    out
+}
+
+/// The module that contains the file. Nested modules are preferred over their parents.
+pub fn module_for_path<'a>(modules: &'a [Module], path: &Path) -> Option<&'a Module> {
+    modules
+        .iter()
+        .filter(|m| path.starts_with(&m.root))
+        .max_by_key(|m| m.root.components().count())
+}
+
+/// The template folders of all modules. The folder of the module that contains `current` is
+/// first, so that includes are resolved in the own module before the other modules.
+pub fn template_folders(
+    modules: &[Module],
+    config: &Config,
+    current: Option<&Path>,
+) -> Vec<PathBuf> {
+    let current = current.and_then(|path| module_for_path(modules, path));
+    let mut out: Vec<PathBuf> = current
+        .iter()
+        .flat_map(|m| m.template_folders(config))
+        .collect();
+    out.extend(
+        modules
+            .iter()
+            .filter(|m| Some(*m) != current)
+            .flat_map(|m| m.template_folders(config)),
+    );
+    out
+}
+
+/// The module folders that are declared in the build files of the folder
+fn declared_modules(folder: &Path) -> Vec<String> {
+    let mut out = vec![];
+    if let Ok(content) = fs::read_to_string(folder.join("pom.xml")) {
+        out.extend(maven_modules(&content));
+    }
+    for settings in ["settings.gradle", "settings.gradle.kts"] {
+        if let Ok(content) = fs::read_to_string(folder.join(settings)) {
+            out.extend(gradle_modules(&content));
+        }
+    }
+    out
+}
+
+/// `<module>web</module>` => `web`
+fn maven_modules(content: &str) -> Vec<String> {
+    let content = remove_between(content, "<!--", "-->");
+    content
+        .split("<module>")
+        .skip(1)
+        .filter_map(|part| part.split_once("</module>"))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// `include("web", ":api:rest")` => `web`, `api/rest`
+fn gradle_modules(content: &str) -> Vec<String> {
+    let content = remove_between(content, "/*", "*/");
+    let mut out = vec![];
+    let mut in_include = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if let Some(rest) = line.strip_prefix("include") {
+            in_include = rest.starts_with(['(', ' ', '\t', '\'', '"']);
+        }
+        if !in_include {
+            continue;
+        }
+        out.extend(string_literals(line).into_iter().map(|name| {
+            name.trim_start_matches(':')
+                .replace(':', "/")
+                .trim()
+                .to_string()
+        }));
+        // multi line includes end with the closing parenthesis or a line without a comma
+        if line.ends_with(')') || !line.ends_with([',', '(']) {
+            in_include = false;
+        }
+    }
+    out.into_iter().filter(|name| !name.is_empty()).collect()
+}
+
+fn string_literals(line: &str) -> Vec<String> {
+    let mut out = vec![];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            out.push(chars.by_ref().take_while(|n| *n != c).collect());
+        }
+    }
+    out
+}
+
+fn remove_between(content: &str, start: &str, end: &str) -> String {
+    let mut out = String::new();
+    let mut rest = content;
+    while let Some(index) = rest.find(start) {
+        out.push_str(&rest[..index]);
+        rest = match rest[index..].find(end) {
+            Some(end_index) => &rest[index + end_index + end.len()..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use pretty_assertions::assert_eq;
+
+    use crate::config::Config;
+
+    use super::{gradle_modules, maven_modules, module_for_path, template_folders, Module};
+
+    #[test]
+    fn maven() {
+        let content = "<project>
+    <modules>
+        <module>api</module>
+        <!-- <module>legacy</module> -->
+        <module> web </module>
+    </modules>
+</project>";
+        assert_eq!(maven_modules(content), vec!["api", "web"]);
+    }
+
+    #[test]
+    fn gradle() {
+        let content = "rootProject.name = 'shop'
+include 'api', ':web'
+// include 'legacy'
+include(
+    \":services:orders\",
+    \"services:billing\"
+)
+includeBuild(\"plugins\")";
+        assert_eq!(
+            gradle_modules(content),
+            vec!["api", "web", "services/orders", "services/billing"]
+        );
+    }
+
+    #[test]
+    fn module_of_template() {
+        let modules = vec![
+            Module {
+                name: String::new(),
+                root: PathBuf::from("/shop"),
+            },
+            Module {
+                name: "web".to_string(),
+                root: PathBuf::from("/shop/web"),
+            },
+        ];
+        let template = Path::new("/shop/web/src/main/resources/templates/index.html");
+        assert_eq!(
+            module_for_path(&modules, template).map(|m| m.name.as_str()),
+            Some("web")
+        );
+        assert_eq!(
+            template_folders(&modules, &Config::default(), Some(template)),
+            vec![
+                PathBuf::from("/shop/web/src/main/resources/templates/"),
+                PathBuf::from("/shop/src/main/resources/templates/"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{Position, Url};
+
+use crate::parser::{
+    fragemnt::{get_fragment_id, template_id},
+    include::{include_at, QuteInclude},
+    java_template::scan_java_templates,
+    qute::QuteDocument,
+    reference_index::ReferenceIndex,
+    template_reference::{scan_includes, TemplateReference},
+};
+
+/// A template or a fragment of it. `("items", Some("row"))` for `items$row`
+pub type TemplateTarget = (String, Option<String>);
+
+/// The references to templates of a java or template file. The id of a template is taken from
+/// the template folder that contains it.
+pub fn scan_file(
+    path: &Path,
+    content: &str,
+    template_folders: &[PathBuf],
+) -> Vec<TemplateReference> {
+    if path.extension().is_some_and(|e| e == "java") {
+        return scan_java_templates(content);
+    }
+    let template = template_id(path, template_folders).unwrap_or_default();
+    scan_includes(content, &template)
+}
+
+/// Indexes the references of the file. Does nothing when the file can not be read.
+pub fn index_file(reference_index: &ReferenceIndex, path: &Path, template_folders: &[PathBuf]) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+    let path = std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let Ok(uri) = Url::from_file_path(&path) else {
+        return;
+    };
+    reference_index.replace_file(uri, scan_file(&path, &content, template_folders));
+}
+
+/// The template or fragment whose references are requested in a template. The include at
+/// the cursor, the fragment whose start tag contains the cursor or the current template.
+pub fn template_target(
+    document: &QuteDocument,
+    offset: usize,
+    current_template: &str,
+) -> TemplateTarget {
+    if let Some(include) = include_at(document, offset) {
+        return match include {
+            QuteInclude::Basic(template) => (template, None),
+            // `{#include $row /}` includes a fragment of the current template
+            QuteInclude::Fragment(fragment) if fragment.template.is_empty() => {
+                (current_template.to_string(), Some(fragment.fragment))
+            }
+            QuteInclude::Fragment(fragment) => (fragment.template, Some(fragment.fragment)),
+        };
+    }
+    let fragment = document
+        .sections_at(offset)
+        .into_iter()
+        .filter(|s| s.start_tag.contains(offset))
+        .find_map(get_fragment_id);
+    (current_template.to_string(), fragment)
+}
+
+/// The template or fragment that is referenced at the position of a java file
+pub fn java_target(content: &str, position: Position) -> Option<TemplateTarget> {
+    scan_java_templates(content)
+        .into_iter()
+        .find(|r| r.range.start <= position && position <= r.range.end)
+        .map(|r| (r.template, r.fragment))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Position;
+
+    use crate::parser::qute;
+
+    use super::{java_target, template_target};
+
+    #[test]
+    fn target_in_template() {
+        let content =
+            "{#include base /}\n{#fragment id=row}<li></li>{/fragment}\n<p></p>{#include $row /}";
+        let document = qute::parse(content);
+        let target = |offset: usize| template_target(&document, offset, "items");
+        assert_eq!(target(12), ("base".to_string(), None));
+        assert_eq!(target(75), ("items".to_string(), Some("row".to_string())));
+        assert_eq!(target(22), ("items".to_string(), Some("row".to_string())));
+        assert_eq!(target(60), ("items".to_string(), None));
+    }
+
+    #[test]
+    fn target_in_java() {
+        let content = "class A {\n    Template items;\n}";
+        assert_eq!(
+            java_target(content, Position::new(1, 15)),
+            Some(("items".to_string(), None))
+        );
+        assert_eq!(java_target(content, Position::new(0, 2)), None);
+    }
+}
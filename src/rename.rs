@@ -0,0 +1,282 @@
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{
+    FileOperationFilter, FileOperationPattern, FileOperationPatternKind,
+    FileOperationRegistrationOptions, Position, Range, TextEdit,
+};
+
+use crate::{
+    config::Config,
+    file_utils::find_files,
+    parser::{
+        fragemnt::get_fragment_id_parameter,
+        include::{include_at, QuteInclude},
+        java_template::scan_java_templates,
+        qute::{LineIndex, QuteDocument},
+        template_reference::TemplateReference,
+    },
+    references::TemplateTarget,
+};
+
+/// The fragment id at the offset of a template with the range of the id. The id of a
+/// `{#fragment}` start tag or the fragment part of `{#include page$item /}`.
+pub fn fragment_at(
+    document: &QuteDocument,
+    index: &LineIndex,
+    offset: usize,
+    current_template: &str,
+) -> Option<(TemplateTarget, Range)> {
+    if let Some(QuteInclude::Fragment(fragment)) = include_at(document, offset) {
+        let value = document
+            .sections_at(offset)
+            .into_iter()
+            .filter(|s| s.name == "include" && s.start_tag.contains(offset))
+            .find_map(|s| s.parameters().iter().find(|p| p.name.is_none()))?;
+        let start = value.value_span.start + fragment.template.len() + 1;
+        if offset < start || offset > value.value_span.end {
+            return None;
+        }
+        let range = index.span(start, value.value_span.end).range;
+        let template = match fragment.template.is_empty() {
+            true => current_template.to_string(),
+            false => fragment.template,
+        };
+        return Some(((template, Some(fragment.fragment)), range));
+    }
+    let id = document
+        .sections_at(offset)
+        .into_iter()
+        .filter_map(get_fragment_id_parameter)
+        .find(|p| p.value_span.contains(offset))?;
+    Some((
+        (current_template.to_string(), Some(id.value.clone())),
+        id.value_span.range,
+    ))
+}
+
+/// The fragment id at the position of a java file. `getFragment("item")` or the fragment of a
+/// checked template method like `items$item`.
+pub fn java_fragment_at(content: &str, position: Position) -> Option<(TemplateTarget, Range)> {
+    scan_java_templates(content).into_iter().find_map(|r| {
+        let range = r.fragment_range?;
+        if position < range.start || position > range.end {
+            return None;
+        }
+        Some(((r.template, r.fragment), range))
+    })
+}
+
+/// The range of the id of the fragment definition
+pub fn fragment_definition(document: &QuteDocument, id: &str) -> Option<Range> {
+    document
+        .sections()
+        .into_iter()
+        .filter_map(get_fragment_id_parameter)
+        .find(|p| p.value == id)
+        .map(|p| p.value_span.range)
+}
+
+/// Fragment ids are used in java method names so they must be java identifiers
+pub fn is_valid_fragment_id(id: &str) -> bool {
+    let mut chars = id.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Renaming templates and folders that may contain templates updates the references
+pub fn will_rename_options(config: &Config) -> FileOperationRegistrationOptions {
+    let filter = |glob: String, matches: FileOperationPatternKind| FileOperationFilter {
+        scheme: Some("file".to_string()),
+        pattern: FileOperationPattern {
+            glob,
+            matches: Some(matches),
+            options: None,
+        },
+    };
+    FileOperationRegistrationOptions {
+        filters: vec![
+            filter(
+                format!("**/*.{{{}}}", config.template_suffixes.join(",")),
+                FileOperationPatternKind::File,
+            ),
+            filter("**".to_string(), FileOperationPatternKind::Folder),
+        ],
+    }
+}
+
+/// The templates that are moved by renaming a file or a folder with their new path
+pub fn renamed_templates(old: &Path, new: &Path, config: &Config) -> Vec<(PathBuf, PathBuf)> {
+    if !old.is_dir() {
+        if config.is_template_suffix(old) && config.is_template_suffix(new) {
+            return vec![(old.to_path_buf(), new.to_path_buf())];
+        }
+        return vec![];
+    }
+    find_files(old)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| config.is_template_suffix(path))
+        .filter_map(|path| {
+            let new_path = new.join(path.strip_prefix(old).ok()?);
+            Some((path, new_path))
+        })
+        .collect()
+}
+
+/// The edit that makes an include or a `@Location` reference the new template. The fragment
+/// and the suffix of the location are kept.
+pub fn template_rename_edit(reference: &TemplateReference, new_template: &str) -> Option<TextEdit> {
+    Some(TextEdit::new(
+        reference.template_range()?,
+        new_template.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    use tower_lsp::lsp_types::{Position, Range, TextEdit, Url};
+
+    use crate::{
+        config::Config,
+        parser::{
+            java_template::scan_java_templates,
+            qute::{self, LineIndex},
+            reference_index::ReferenceIndex,
+            template_reference::scan_includes,
+        },
+    };
+
+    use super::{
+        fragment_at, fragment_definition, is_valid_fragment_id, java_fragment_at,
+        renamed_templates, template_rename_edit,
+    };
+
+    #[test]
+    fn fragment_in_template() {
+        let content = "{#include page$item /}\n{#fragment id=row}{/fragment}";
+        let document = qute::parse(content);
+        let index = LineIndex::new(content);
+        let at = |offset: usize| fragment_at(&document, &index, offset, "items");
+        assert_eq!(
+            at(16),
+            Some((
+                ("page".to_string(), Some("item".to_string())),
+                Range::new(Position::new(0, 15), Position::new(0, 19))
+            ))
+        );
+        // the template part of the include
+        assert_eq!(at(11), None);
+        assert_eq!(
+            at(38),
+            Some((
+                ("items".to_string(), Some("row".to_string())),
+                Range::new(Position::new(1, 14), Position::new(1, 17))
+            ))
+        );
+        assert_eq!(
+            fragment_definition(&document, "row"),
+            Some(Range::new(Position::new(1, 14), Position::new(1, 17)))
+        );
+    }
+
+    #[test]
+    fn fragment_in_java() {
+        let content =
+            "class A {\n    Template items;\n    void a() { items.getFragment(\"row\"); }\n}";
+        assert_eq!(
+            java_fragment_at(content, Position::new(2, 35)),
+            Some((
+                ("items".to_string(), Some("row".to_string())),
+                Range::new(Position::new(2, 34), Position::new(2, 37))
+            ))
+        );
+        assert_eq!(
+            java_fragment_at(
+                "class A { void a() { x.getFragment(\"row\"); } }",
+                Position::new(0, 37)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn rename_local_include_of_one_template() {
+        let content = "{#fragment id=row}<li></li>{/fragment}\n{#include $row /}";
+        let references = ReferenceIndex::new();
+        let a = Url::parse("file:///templates/a.html").unwrap();
+        let b = Url::parse("file:///templates/b.html").unwrap();
+        references.replace_file(a.clone(), scan_includes(content, "a"));
+        references.replace_file(b, scan_includes(content, "b"));
+        let document = qute::parse(content);
+        let index = LineIndex::new(content);
+        let (target, range) = fragment_at(&document, &index, 51, "a").unwrap();
+        assert_eq!(target, ("a".to_string(), Some("row".to_string())));
+        assert_eq!(
+            references
+                .find("a", Some("row"))
+                .into_iter()
+                .map(|(uri, r)| (uri, r.fragment_range))
+                .collect::<Vec<_>>(),
+            vec![(a, Some(range))]
+        );
+    }
+
+    #[test]
+    fn valid_fragment_id() {
+        assert!(is_valid_fragment_id("item_row2"));
+        assert!(!is_valid_fragment_id("2row"));
+        assert!(!is_valid_fragment_id("item-row"));
+        assert!(!is_valid_fragment_id(""));
+    }
+
+    #[test]
+    fn rename_template_edits() {
+        let include = &scan_includes("{#include snippets/tailwind$head /}", "page")[0];
+        assert_eq!(
+            template_rename_edit(include, "layout/tailwind"),
+            Some(TextEdit::new(
+                Range::new(Position::new(0, 10), Position::new(0, 27)),
+                "layout/tailwind".to_string()
+            ))
+        );
+        let java = scan_java_templates(
+            "class A {\n  @Location(\"snippets/tailwind.html\") Template a;\n  Template b;\n}",
+        );
+        assert_eq!(
+            template_rename_edit(&java[0], "layout/tailwind"),
+            Some(TextEdit::new(
+                Range::new(Position::new(1, 13), Position::new(1, 30)),
+                "layout/tailwind".to_string()
+            ))
+        );
+        assert_eq!(template_rename_edit(&java[1], "layout/tailwind"), None);
+    }
+
+    #[test]
+    fn renamed_templates_of_folder() {
+        let config = Config::default();
+        let resources = PathBuf::from("plugins/vscode/client/testFixture/src/main/resources");
+        let renamed = renamed_templates(
+            &resources.join("templates"),
+            &resources.join("pages"),
+            &config,
+        );
+        assert!(renamed.contains(&(
+            resources.join("templates/index.html"),
+            resources.join("pages/index.html")
+        )));
+        assert_eq!(
+            renamed_templates(
+                &PathBuf::from("items.html"),
+                &PathBuf::from("items.txt"),
+                &config
+            ),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use tower_lsp::lsp_types::{Range, SemanticToken, SemanticTokenType, SemanticTokensLegend};
+
+use crate::parser::qute::{
+    self, section_expressions, Expression, ExpressionPartKind, LineIndex, QuteNode, Section,
+};
+
+/// The order of the types is the index that is sent to the editor
+const TOKEN_TYPES: [SemanticTokenType; 9] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::PARAMETER,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenType {
+    /// `{#for`, `{#else}` and `{/for}`
+    SectionTag,
+    /// `inject` in `{inject:bean}`
+    Namespace,
+    Property,
+    VirtualMethod,
+    String,
+    Number,
+    Comment,
+    /// `org.acme.Item` in `{@org.acme.Item item}`
+    Type,
+    /// `item` in `{@org.acme.Item item}`
+    Parameter,
+    /// `true`, `false` and `null`. Sent as keywords like most languages do.
+    Constant,
+}
+
+impl TokenType {
+    const fn index(self) -> u32 {
+        match self {
+            TokenType::SectionTag => 0,
+            TokenType::Namespace => 1,
+            TokenType::Property => 2,
+            TokenType::VirtualMethod => 3,
+            TokenType::String => 4,
+            TokenType::Number => 5,
+            TokenType::Comment => 6,
+            TokenType::Type => 7,
+            TokenType::Parameter => 8,
+            TokenType::Constant => 0,
+        }
+    }
+}
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![],
+    }
+}
+
+/// The semantic tokens of a template. Only the tokens on the lines of the range when a range
+/// is given.
+pub fn semantic_tokens(content: &str, range: Option<Range>) -> Vec<SemanticToken> {
+    let index = LineIndex::new(content);
+    let mut tokens = vec![];
+    for node in qute::parse(content).walk() {
+        node_tokens(content, &index, node, &mut tokens);
+    }
+    tokens.sort_by_key(|t| t.0);
+    let mut out = vec![];
+    let mut previous = (0, 0);
+    for (start, end, token_type) in tokens {
+        for (start, end) in single_lines(content, start, end) {
+            let start = index.position(start);
+            let end = index.position(end);
+            let in_range =
+                range.is_none_or(|r| r.start.line <= start.line && start.line <= r.end.line);
+            if !in_range || end.character <= start.character {
+                continue;
+            }
+            let delta_line = start.line - previous.0;
+            let delta_start = match delta_line {
+                0 => start.character - previous.1,
+                _ => start.character,
+            };
+            out.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length: end.character - start.character,
+                token_type: token_type.index(),
+                token_modifiers_bitset: 0,
+            });
+            previous = (start.line, start.character);
+        }
+    }
+    out
+}
+
+/// Tokens of the node without its children. Byte offsets of the start and the end.
+fn node_tokens(
+    content: &str,
+    index: &LineIndex,
+    node: &QuteNode,
+    out: &mut Vec<(usize, usize, TokenType)>,
+) {
+    match node {
+        QuteNode::Comment(span) => out.push((span.start, span.end, TokenType::Comment)),
+        QuteNode::Expression(expression) => expression_tokens(expression, out),
+        QuteNode::ParameterDeclaration(declaration) => {
+            let java_type = &declaration.java_type.span;
+            let name = &declaration.name.span;
+            out.push((java_type.start, java_type.end, TokenType::Type));
+            out.push((name.start, name.end, TokenType::Parameter));
+        }
+        QuteNode::Section(section) => section_tokens(content, index, section, out),
+        QuteNode::Text(_) | QuteNode::Unparsed(_) => {}
+    }
+}
+
+/// `{#name` of the start tag, the tags of the other blocks, the end tag and the expressions in
+/// the parameters
+fn section_tokens(
+    content: &str,
+    index: &LineIndex,
+    section: &Section,
+    out: &mut Vec<(usize, usize, TokenType)>,
+) {
+    out.push((
+        section.start_tag.start,
+        section.name_span.end,
+        TokenType::SectionTag,
+    ));
+    for expression in section_expressions(index, section) {
+        expression_tokens(&expression, out);
+    }
+    for block in section.blocks.iter().skip(1) {
+        let tag = &block.tag;
+        // `{#else` of `{#else if a}`
+        let label_end = content[tag.start..tag.end]
+            .find(|c: char| c.is_whitespace() || c == '}')
+            .map_or(tag.end, |i| tag.start + i);
+        out.push((tag.start, label_end, TokenType::SectionTag));
+    }
+    if let Some(end_tag) = &section.end_tag {
+        out.push((end_tag.start, end_tag.end, TokenType::SectionTag));
+    }
+}
+
+fn expression_tokens(expression: &Expression, out: &mut Vec<(usize, usize, TokenType)>) {
+    if let Some(namespace) = &expression.namespace {
+        out.push((
+            namespace.span.start,
+            namespace.span.end,
+            TokenType::Namespace,
+        ));
+    }
+    for part in &expression.parts {
+        let token_type = match &part.kind {
+            ExpressionPartKind::Property => TokenType::Property,
+            ExpressionPartKind::VirtualMethod(parameters) => {
+                for parameter in parameters {
+                    expression_tokens(parameter, out);
+                }
+                TokenType::VirtualMethod
+            }
+            ExpressionPartKind::Literal if part.name.starts_with(['\'', '"']) => TokenType::String,
+            ExpressionPartKind::Literal
+                if matches!(part.name.as_str(), "true" | "false" | "null") =>
+            {
+                TokenType::Constant
+            }
+            ExpressionPartKind::Literal => TokenType::Number,
+        };
+        out.push((part.span.start, part.span.end, token_type));
+    }
+}
+
+/// Editors do not have to support tokens that span multiple lines
+fn single_lines(content: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut out = vec![];
+    let mut line_start = start;
+    for (i, c) in content[start..end].char_indices() {
+        if c == '\n' {
+            let line_end = start + i;
+            let line_end = match content[..line_end].ends_with('\r') {
+                true => line_end - 1,
+                false => line_end,
+            };
+            out.push((line_start, line_end));
+            line_start = start + i + 1;
+        }
+    }
+    out.push((line_start, end));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{Position, Range, SemanticTokenType};
+
+    use super::{semantic_tokens, TokenType, TOKEN_TYPES};
+
+    /// Decodes the relative positions again as `(line, start, length, type)`
+    fn decode(content: &str, range: Option<Range>) -> Vec<(u32, u32, u32, u32)> {
+        let mut line = 0;
+        let mut start = 0;
+        semantic_tokens(content, range)
+            .into_iter()
+            .map(|t| {
+                if t.delta_line > 0 {
+                    start = 0;
+                }
+                line += t.delta_line;
+                start += t.delta_start;
+                (line, start, t.length, t.token_type)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn semantic_tokens_basic() {
+        let content = "{@org.acme.Item item}
+{#if item.active}
+  {inject:bean.name.or('none')}
+{#else}
+  {! not
+  active !}
+{/if}";
+        assert_eq!(
+            decode(content, None),
+            vec![
+                (0, 2, 13, TokenType::Type.index()),
+                (0, 16, 4, TokenType::Parameter.index()),
+                (1, 0, 4, TokenType::SectionTag.index()),
+                (1, 5, 4, TokenType::Property.index()),
+                (1, 10, 6, TokenType::Property.index()),
+                (2, 3, 6, TokenType::Namespace.index()),
+                (2, 10, 4, TokenType::Property.index()),
+                (2, 15, 4, TokenType::Property.index()),
+                (2, 20, 2, TokenType::VirtualMethod.index()),
+                (2, 23, 6, TokenType::String.index()),
+                (3, 0, 6, TokenType::SectionTag.index()),
+                (4, 2, 6, TokenType::Comment.index()),
+                (5, 0, 11, TokenType::Comment.index()),
+                (6, 0, 5, TokenType::SectionTag.index()),
+            ]
+        );
+        let range = Range::new(Position::new(3, 0), Position::new(4, 0));
+        assert_eq!(
+            decode(content, Some(range)),
+            vec![
+                (3, 0, 6, TokenType::SectionTag.index()),
+                (4, 2, 6, TokenType::Comment.index()),
+            ]
+        );
+    }
+
+    #[test]
+    fn semantic_tokens_of_section_parameters() {
+        let content = "{#for i in item.children}{#let x=i.name}{/let}{/for}
+{#if !i.active || (count gt 1)}{#else if i.label('a')}{/if}";
+        let property = TokenType::Property.index();
+        assert_eq!(
+            decode(content, None)
+                .into_iter()
+                .filter(|t| t.3 != TokenType::SectionTag.index())
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 11, 4, property),
+                (0, 16, 8, property),
+                (0, 33, 1, property),
+                (0, 35, 4, property),
+                (1, 6, 1, property),
+                (1, 8, 6, property),
+                (1, 19, 5, property),
+                (1, 28, 1, TokenType::Number.index()),
+                (1, 41, 1, property),
+                (1, 43, 5, TokenType::VirtualMethod.index()),
+                (1, 49, 3, TokenType::String.index()),
+            ]
+        );
+    }
+
+    #[test]
+    fn constants_are_keywords() {
+        let out = decode("{item.or(null)}", None);
+        assert_eq!(out.last().map(|t| (t.1, t.2)), Some((9, 4)));
+        assert_eq!(
+            out.last().map(|t| TOKEN_TYPES[t.3 as usize].clone()),
+            Some(SemanticTokenType::KEYWORD)
+        );
+    }
+}
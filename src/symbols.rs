@@ -0,0 +1,321 @@
+use std::path::Path;
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use tower_lsp::lsp_types::{DocumentSymbol, Location, Range, SymbolInformation, SymbolKind, Url};
+use tree_sitter::Node;
+
+use crate::{
+    extraction::{get_id_of_node, get_tree},
+    parser::{
+        fragemnt::{get_fragment_id, Fragment},
+        qute::{self, LineIndex, Section},
+        route::Route,
+    },
+};
+
+/// The outline of a template. Fragments, inserts, includes, loops, conditionals and html
+/// elements with an id nested the way they are nested in the template.
+pub fn document_symbols(content: &str) -> Vec<DocumentSymbol> {
+    let index = LineIndex::new(content);
+    let mut items: Vec<OutlineItem> = qute::parse(content)
+        .sections()
+        .into_iter()
+        .filter_map(|section| {
+            Some(OutlineItem {
+                start: section.span.start,
+                end: section.span.end,
+                symbol: section_symbol(content, section)?,
+                children: vec![],
+            })
+        })
+        .collect();
+    let language = tree_sitter_html::language();
+    if let Ok(tree) = get_tree(content, &language) {
+        element_items(tree.root_node(), content, &index, &mut items);
+    }
+    // parents start first and end last
+    items.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    let mut out = vec![];
+    for item in items {
+        insert_item(&mut out, item);
+    }
+    out.into_iter().map(|item| item.into_symbol()).collect()
+}
+
+fn section_symbol(content: &str, section: &Section) -> Option<DocumentSymbol> {
+    let kind = match section.name.as_str() {
+        "fragment" => SymbolKind::FUNCTION,
+        "insert" => SymbolKind::NAMESPACE,
+        "include" => SymbolKind::FILE,
+        "for" | "each" => SymbolKind::ARRAY,
+        "if" | "when" | "switch" => SymbolKind::BOOLEAN,
+        _ => return None,
+    };
+    let name = match section.name.as_str() {
+        "fragment" => get_fragment_id(section),
+        _ => parameters_text(content, section),
+    };
+    Some(symbol(
+        name.unwrap_or(section.name.clone()),
+        section.name.clone(),
+        kind,
+        section.span.range,
+        section.start_tag.range,
+    ))
+}
+
+/// `item in items` of `{#for item in items}`
+fn parameters_text(content: &str, section: &Section) -> Option<String> {
+    let first = section.parameters().first()?;
+    let last = section.parameters().last()?;
+    content
+        .get(first.span.start..last.span.end)
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn element_items(node: Node, content: &str, index: &LineIndex, out: &mut Vec<OutlineItem>) {
+    if node.kind() == "element" {
+        if let Some(symbol) = element_symbol(node, content, index) {
+            out.push(OutlineItem {
+                start: node.start_byte(),
+                end: node.end_byte(),
+                symbol,
+                children: vec![],
+            });
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        element_items(child, content, index, out);
+    }
+}
+
+/// `<div id="items">` is shown as `items`
+fn element_symbol(node: Node, content: &str, index: &LineIndex) -> Option<DocumentSymbol> {
+    let start_tag = node.child(0).filter(|c| c.kind() == "start_tag")?;
+    // only the start tag so the ids of children are not used
+    let id = get_id_of_node(&tree_sitter_html::language(), start_tag, content).ok()?;
+    let tag_name = start_tag
+        .named_child(0)
+        .filter(|c| c.kind() == "tag_name")
+        .and_then(|c| c.utf8_text(content.as_bytes()).ok())
+        .unwrap_or("element");
+    let range = index.span(node.start_byte(), node.end_byte()).range;
+    let selection_range = index
+        .span(start_tag.start_byte(), start_tag.end_byte())
+        .range;
+    Some(symbol(
+        id,
+        tag_name.to_string(),
+        SymbolKind::OBJECT,
+        range,
+        selection_range,
+    ))
+}
+
+/// A symbol with the byte offsets that are used to nest it
+struct OutlineItem {
+    start: usize,
+    end: usize,
+    symbol: DocumentSymbol,
+    children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    fn contains(&self, other: &OutlineItem) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    fn into_symbol(self) -> DocumentSymbol {
+        let mut symbol = self.symbol;
+        if !self.children.is_empty() {
+            symbol.children = Some(self.children.into_iter().map(|c| c.into_symbol()).collect());
+        }
+        symbol
+    }
+}
+
+/// Adds the item to the last item that contains it or to the end of the list
+fn insert_item(items: &mut Vec<OutlineItem>, item: OutlineItem) {
+    match items.last_mut().filter(|parent| parent.contains(&item)) {
+        Some(parent) => insert_item(&mut parent.children, item),
+        None => items.push(item),
+    }
+}
+
+fn symbol(
+    name: String,
+    detail: String,
+    kind: SymbolKind,
+    range: Range,
+    selection_range: Range,
+) -> DocumentSymbol {
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name,
+        detail: Some(detail),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children: None,
+    }
+}
+
+/// The symbols whose name matches the query fuzzy. The best matches come first.
+pub fn workspace_symbols(query: &str, symbols: Vec<SymbolInformation>) -> Vec<SymbolInformation> {
+    let matcher = SkimMatcherV2::default();
+    let mut out: Vec<_> = symbols
+        .into_iter()
+        .filter_map(|symbol| Some((matcher.fuzzy_match(&symbol.name, query)?, symbol)))
+        .collect();
+    out.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    out.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// `GET /items/{id}` at the java method. None when the implementation is not known.
+pub fn route_symbol(route: &Route) -> Option<SymbolInformation> {
+    Some(symbol_information(
+        format!("{} {}", route.method.to_string(), route.path),
+        SymbolKind::METHOD,
+        route.implementation.clone()?,
+        Some(route.class_name.clone()),
+    ))
+}
+
+/// `folder/file` at the start of the template
+pub fn template_symbol(path: &Path, template: String) -> Option<SymbolInformation> {
+    let uri = Url::from_file_path(path).ok()?;
+    Some(symbol_information(
+        template,
+        SymbolKind::FILE,
+        Location::new(uri, Range::default()),
+        None,
+    ))
+}
+
+/// `folder/file$frag` at the id of the fragment
+pub fn fragment_symbol(fragment: &Fragment) -> Option<SymbolInformation> {
+    let uri = Url::from_file_path(&fragment.source).ok()?;
+    let container = fragment.id.split_once('$').map(|(t, _)| t.to_string());
+    Some(symbol_information(
+        fragment.id.clone(),
+        SymbolKind::FUNCTION,
+        Location::new(uri, fragment.range),
+        container,
+    ))
+}
+
+fn symbol_information(
+    name: String,
+    kind: SymbolKind,
+    location: Location,
+    container_name: Option<String>,
+) -> SymbolInformation {
+    #[allow(deprecated)]
+    SymbolInformation {
+        name,
+        kind,
+        tags: None,
+        deprecated: None,
+        location,
+        container_name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{DocumentSymbol, Location, Position, Range, Url};
+
+    use crate::parser::{
+        fragemnt::Fragment,
+        route::{HttpMethod, Route},
+    };
+
+    use super::{document_symbols, fragment_symbol, route_symbol, workspace_symbols};
+
+    fn outline(symbols: &[DocumentSymbol], depth: usize, out: &mut Vec<String>) {
+        for symbol in symbols {
+            out.push(format!(
+                "{}{} {:?} {}",
+                "  ".repeat(depth),
+                symbol.name,
+                symbol.kind,
+                symbol.detail.clone().unwrap_or_default()
+            ));
+            outline(
+                symbol.children.as_deref().unwrap_or_default(),
+                depth + 1,
+                out,
+            );
+        }
+    }
+
+    #[test]
+    fn document_symbols_nested() {
+        let content = "{#include base}
+{#insert title}Items{/insert}
+<ul id=\"items\">
+  {#for item in items}
+  {#fragment id=row}
+  <li><span id=\"name\">{item.name}</span></li>
+  {/fragment}
+  {/for}
+</ul>
+{#if items.isEmpty}<p>none</p>{/if}
+{/include}";
+        let mut out = vec![];
+        outline(&document_symbols(content), 0, &mut out);
+        assert_eq!(
+            out,
+            vec![
+                "base File include",
+                "  title Namespace insert",
+                "  items Object ul",
+                "    item in items Array for",
+                "      row Function fragment",
+                "        name Object span",
+                "  items.isEmpty Boolean if",
+            ]
+        );
+    }
+
+    #[test]
+    fn workspace_symbols_fuzzy() {
+        let uri = Url::parse("file:///project/src/main/java/ItemResource.java").unwrap();
+        let route = |method: HttpMethod, path: &str| Route {
+            implementation: Some(Location::new(uri.clone(), Range::default())),
+            method,
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let range = Range::new(Position::new(3, 14), Position::new(3, 17));
+        let fragment = Fragment {
+            id: "items$row".to_string(),
+            source: "/project/src/main/resources/templates/items.html".to_string(),
+            range,
+        };
+        let symbols = vec![
+            route_symbol(&route(HttpMethod::Get, "/items/{id}")),
+            route_symbol(&route(HttpMethod::Post, "/items")),
+            fragment_symbol(&fragment),
+            route_symbol(&Route {
+                implementation: None,
+                ..Default::default()
+            }),
+        ];
+        let out = workspace_symbols("itmrow", symbols.iter().flatten().cloned().collect());
+        assert_eq!(
+            out.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["items$row"]
+        );
+        assert_eq!(out[0].location.range, range);
+        let out = workspace_symbols("post items", symbols.into_iter().flatten().collect());
+        assert_eq!(
+            out.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["POST /items"]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use ropey::Rope;
+use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+
+/// Applies a change of the client to the document. Changes without a range replace the whole
+/// document.
+pub fn apply_change(rope: &mut Rope, change: &TextDocumentContentChangeEvent) {
+    let Some(range) = change.range else {
+        *rope = Rope::from_str(&change.text);
+        return;
+    };
+    let start = position_to_char(rope, range.start);
+    let end = position_to_char(rope, range.end).max(start);
+    rope.remove(start..end);
+    rope.insert(start, &change.text);
+}
+
+/// Converts a lsp position to a char index of the rope. The character of a lsp position counts
+/// utf-16 code units. Positions after the end of a line are moved to the end of the line and
+/// positions after the last line to the end of the document.
+pub fn position_to_char(rope: &Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= rope.len_lines() {
+        return rope.len_chars();
+    }
+    let line_start = rope.line_to_char(line);
+    let line_end = line_start + line_len(rope, line);
+    let line_start_cu = rope.char_to_utf16_cu(line_start);
+    let line_end_cu = rope.char_to_utf16_cu(line_end);
+    let cu = (line_start_cu + position.character as usize).min(line_end_cu);
+    rope.utf16_cu_to_char(cu)
+}
+
+/// Number of chars in the line without the line break
+fn line_len(rope: &Rope, line: usize) -> usize {
+    let slice = rope.line(line);
+    let len = slice.len_chars();
+    let line_break = slice
+        .chars_at(len)
+        .reversed()
+        .take(2)
+        .take_while(|c| *c == '\n' || *c == '\r')
+        .count();
+    len - line_break
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ropey::Rope;
+    use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+    use super::{apply_change, position_to_char};
+
+    fn change(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            )),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn apply_incremental_changes() {
+        let mut rope = Rope::from_str("{#if a}\n  {a}\n{/if}\n");
+        apply_change(&mut rope, &change((1, 3), (1, 4), "item.name"));
+        apply_change(&mut rope, &change((0, 5), (0, 6), "item"));
+        apply_change(&mut rope, &change((2, 5), (2, 5), "\n<p>done</p>"));
+        assert_eq!(
+            rope.to_string(),
+            "{#if item}\n  {item.name}\n{/if}\n<p>done</p>\n"
+        );
+    }
+
+    #[test]
+    fn apply_full_change() {
+        let mut rope = Rope::from_str("old");
+        apply_change(
+            &mut rope,
+            &TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "new".to_string(),
+            },
+        );
+        assert_eq!(rope.to_string(), "new");
+    }
+
+    #[test]
+    fn utf16_positions() {
+        // 😀 is two utf-16 code units but one char
+        let mut rope = Rope::from_str("<p>😀 {name}</p>\r\nä{b}");
+        assert_eq!(position_to_char(&rope, Position::new(0, 6)), 5);
+        assert_eq!(position_to_char(&rope, Position::new(0, 100)), 15);
+        assert_eq!(position_to_char(&rope, Position::new(1, 1)), 18);
+        assert_eq!(position_to_char(&rope, Position::new(5, 0)), 21);
+        apply_change(&mut rope, &change((0, 7), (0, 11), "title"));
+        apply_change(&mut rope, &change((1, 2), (1, 3), "c"));
+        assert_eq!(rope.to_string(), "<p>😀 {title}</p>\r\nä{c}");
+    }
+}
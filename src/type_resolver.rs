@@ -0,0 +1,241 @@
+use crate::{
+    hover::loop_alias,
+    parser::{
+        checked_template_index::CheckedTemplateIndex,
+        fragemnt::get_fragment_id,
+        java_type::element_type,
+        java_type_index::JavaTypeIndex,
+        qute::{
+            parse_parameter_expression, Expression, ExpressionPartKind, LineIndex, QuteDocument,
+            QuteNode, Section,
+        },
+    },
+};
+
+/// Resolves the java types of the expressions of a template. The types of the first parts come
+/// from loop aliases, parameter declarations like `{@org.acme.Item item}` and the parameters of
+/// the checked template.
+pub struct TypeResolver<'a> {
+    document: &'a QuteDocument,
+    index: LineIndex<'a>,
+    /// `folder/file` of the template
+    template: &'a str,
+    checked_templates: &'a CheckedTemplateIndex,
+    pub types: &'a JavaTypeIndex,
+}
+
+impl<'a> TypeResolver<'a> {
+    pub fn new(
+        content: &'a str,
+        document: &'a QuteDocument,
+        template: &'a str,
+        checked_templates: &'a CheckedTemplateIndex,
+        types: &'a JavaTypeIndex,
+    ) -> Self {
+        Self {
+            document,
+            index: LineIndex::new(content),
+            template,
+            checked_templates,
+            types,
+        }
+    }
+
+    /// The java type of a name that starts an expression at the offset. The alias of the
+    /// innermost loop wins over the declarations of the template.
+    pub fn root_type(&self, name: &str, offset: usize) -> Option<String> {
+        let sections = self.document.sections_at(offset);
+        // the parameters of a section are not in its scope
+        for section in sections.iter().rev().filter(|s| offset >= s.start_tag.end) {
+            if matches!(section.name.as_str(), "let" | "set") && section.parameter(name).is_some() {
+                return None;
+            }
+            if loop_alias(section) == Some(name) {
+                return element_type(&self.iterable_type(section)?);
+            }
+        }
+        let declaration = self
+            .document
+            .walk()
+            .into_iter()
+            .find_map(|node| match node {
+                QuteNode::ParameterDeclaration(d) if d.name.value == name => {
+                    Some(d.java_type.value.clone())
+                }
+                _ => None,
+            });
+        if declaration.is_some() {
+            return declaration;
+        }
+        let fragments: Vec<String> = sections.into_iter().filter_map(get_fragment_id).collect();
+        let (checked_template, parameter) =
+            self.checked_templates
+                .parameter(self.template, &fragments, name)?;
+        // `Item` of `List<Item>` is resolved from the package and imports of the class
+        let Some(class) = self.types.find(&checked_template.qualified_class_name()) else {
+            return Some(parameter.java_type);
+        };
+        Some(self.types.qualify(&parameter.java_type, &class))
+    }
+
+    /// The expression a `{#for}` or `{#each}` section iterates
+    pub fn iterable(&self, section: &Section) -> Option<Expression> {
+        let parameter = match section.name.as_str() {
+            "for" => section.parameters().get(2)?,
+            "each" => section.parameters().first()?,
+            _ => return None,
+        };
+        Some(parse_parameter_expression(&self.index, parameter))
+    }
+
+    fn iterable_type(&self, section: &Section) -> Option<String> {
+        let expression = self.iterable(section)?;
+        let types = self.part_types(&expression);
+        (types.len() == expression.parts.len())
+            .then(|| types.last().cloned())
+            .flatten()
+    }
+
+    /// The java type after each part of the expression. Stops at the first part that can not be
+    /// resolved, so `{item.name.or('x')}` only returns the types of `item` and `name`.
+    pub fn part_types(&self, expression: &Expression) -> Vec<String> {
+        let mut out = vec![];
+        if expression.namespace.is_some() {
+            return out;
+        }
+        let mut parts = expression.parts.iter();
+        let Some(root) = parts.next() else {
+            return out;
+        };
+        if root.kind != ExpressionPartKind::Property {
+            return out;
+        }
+        let Some(root_type) = self.root_type(&root.name, expression.span.start) else {
+            return out;
+        };
+        out.push(root_type);
+        for part in parts {
+            let owner = &out[out.len() - 1];
+            let member = match &part.kind {
+                ExpressionPartKind::Property => self.types.property(owner, &part.name),
+                ExpressionPartKind::VirtualMethod(_) => self.types.method(owner, &part.name),
+                ExpressionPartKind::Literal => None,
+            };
+            let Some(member) = member else {
+                break;
+            };
+            out.push(member.java_type);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::Url;
+
+    use crate::parser::{
+        checked_template::scan_checked_templates, checked_template_index::CheckedTemplateIndex,
+        java_type::scan_java_types, java_type_index::JavaTypeIndex, qute, qute::QuteNode,
+    };
+
+    use super::TypeResolver;
+
+    #[test]
+    fn types_of_declarations_checked_parameters_and_loops() {
+        let java = "package org.acme;
+public class Item {
+    public String name;
+    public List<Item> children;
+}
+public class ItemResource {
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items);
+    }
+}";
+        let types = JavaTypeIndex::new();
+        let checked_templates = CheckedTemplateIndex::new();
+        let uri = Url::parse("file:///project/Item.java").unwrap();
+        types.replace_file(uri.clone(), scan_java_types(java));
+        checked_templates.replace_file(uri, scan_checked_templates(java));
+        let content = "{@org.acme.Item item}
+{#for i in item.children}{i.name}{/for}
+{#each items}{it.children}{/each}";
+        let document = qute::parse(content);
+        let resolver = TypeResolver::new(
+            content,
+            &document,
+            "ItemResource/list",
+            &checked_templates,
+            &types,
+        );
+        let types: Vec<Vec<String>> = document
+            .walk()
+            .into_iter()
+            .filter_map(|node| match node {
+                QuteNode::Expression(expression) => Some(resolver.part_types(expression)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                vec!["org.acme.Item".to_string(), "String".to_string()],
+                vec![
+                    "org.acme.Item".to_string(),
+                    "List<org.acme.Item>".to_string()
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn checked_parameters_from_the_package_of_the_resource() {
+        let types = JavaTypeIndex::new();
+        let checked_templates = CheckedTemplateIndex::new();
+        let files = [
+            (
+                "a/Item",
+                "package org.a;\npublic class Item { public String a; }",
+            ),
+            (
+                "b/Item",
+                "package org.b;\npublic class Item { public String b; }",
+            ),
+            (
+                "b/ItemResource",
+                "package org.b;
+public class ItemResource {
+    @CheckedTemplate
+    static class Templates {
+        public static native TemplateInstance list(List<Item> items);
+    }
+}",
+            ),
+        ];
+        for (name, java) in files {
+            let uri = Url::parse(&format!("file:///project/{name}.java")).unwrap();
+            types.replace_file(uri.clone(), scan_java_types(java));
+            checked_templates.replace_file(uri, scan_checked_templates(java));
+        }
+        let content = "{#for item in items}{item.b}{/for}";
+        let document = qute::parse(content);
+        let resolver = TypeResolver::new(
+            content,
+            &document,
+            "ItemResource/list",
+            &checked_templates,
+            &types,
+        );
+        assert_eq!(
+            resolver.root_type("items", 0),
+            Some("List<org.b.Item>".to_string())
+        );
+        assert_eq!(
+            resolver.root_type("item", 21),
+            Some("org.b.Item".to_string())
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use tower_lsp::lsp_types::InitializeParams;
+
+/// Folder of the qute templates relative to the project root
+pub static TEMPLATE_FOLDER: &str = "src/main/resources/templates/";
+/// Folder of the java sources relative to the project root
+pub static JAVA_FOLDER: &str = "src/main/java/";
+
+/// The root of the workspace that was opened by the editor. The first workspace folder is
+/// preferred over the deprecated root uri. Falls back to the working directory of the process.
+pub fn get_root(params: &InitializeParams) -> PathBuf {
+    let folder = params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| folder.uri.clone());
+    #[allow(deprecated)]
+    let uri = folder.or_else(|| params.root_uri.clone());
+    uri.and_then(|uri| uri.to_file_path().ok())
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+    use tower_lsp::lsp_types::{InitializeParams, Url, WorkspaceFolder};
+
+    use super::get_root;
+
+    #[test]
+    #[allow(deprecated)]
+    fn root_prefers_workspace_folder() {
+        let mut params = InitializeParams {
+            root_uri: Some(Url::parse("file:///home/user/project").unwrap()),
+            ..InitializeParams::default()
+        };
+        assert_eq!(get_root(&params), PathBuf::from("/home/user/project"));
+        params.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::parse("file:///home/user/workspace").unwrap(),
+            name: "workspace".to_string(),
+        }]);
+        assert_eq!(get_root(&params), PathBuf::from("/home/user/workspace"));
+    }
+}